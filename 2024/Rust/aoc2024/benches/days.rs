@@ -0,0 +1,95 @@
+use aoc2024::commands::{day1, day2, day3, day4, day5, day6, day7};
+use aoc2024::input::read_input;
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+use std::io;
+use std::path::Path;
+
+// Read the way the CLI reads it, so the benchmarks measure the same normalised bytes.
+fn input(day: u8) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../problems")
+        .join(format!("day{}-1.txt", day));
+    read_input(&path).unwrap_or_else(|error| panic!("{}: {}", path.display(), error))
+}
+
+// Benchmarks parsing on its own and each part on an already parsed input, so a change to one
+// shows up separately from the others.
+fn bench_day<T, A>(
+    c: &mut Criterion,
+    day: u8,
+    sample_size: usize,
+    parse: impl Fn(&str) -> Result<T, io::Error>,
+    part_1: impl Fn(&T) -> Result<A, io::Error>,
+    part_2: impl Fn(&T) -> Result<A, io::Error>,
+) {
+    let contents = input(day);
+    let parsed = parse(&contents).unwrap();
+
+    let mut group = c.benchmark_group(format!("day{}", day));
+    group.sample_size(sample_size);
+    group.bench_function("parse", |b| b.iter(|| parse(black_box(&contents)).unwrap()));
+    group.bench_function("part_1", |b| b.iter(|| part_1(black_box(&parsed)).unwrap()));
+    group.bench_function("part_2", |b| b.iter(|| part_2(black_box(&parsed)).unwrap()));
+    group.finish();
+}
+
+fn day1(c: &mut Criterion) {
+    bench_day(c, 1, 100, day1::parse, day1::part_1, day1::part_2);
+}
+
+fn day2(c: &mut Criterion) {
+    bench_day(
+        c,
+        2,
+        100,
+        day2::parse,
+        |reports| day2::part_1(reports),
+        |reports| day2::part_2(reports),
+    );
+}
+
+fn day3(c: &mut Criterion) {
+    bench_day(
+        c,
+        3,
+        100,
+        day3::parse,
+        |tokens| day3::part_1(tokens),
+        |tokens| day3::part_2(tokens),
+    );
+}
+
+fn day4(c: &mut Criterion) {
+    bench_day(c, 4, 100, day4::parse, day4::part_1, day4::part_2);
+}
+
+fn day5(c: &mut Criterion) {
+    bench_day(
+        c,
+        5,
+        100,
+        day5::parse,
+        |(rules, updates)| day5::part_1(rules, updates),
+        |(rules, updates)| day5::part_2(rules, updates),
+    );
+}
+
+// Part 2 of days 6 and 7 take a good fraction of a second, so they get the smallest sample.
+fn day6(c: &mut Criterion) {
+    bench_day(c, 6, 10, day6::parse, day6::part_1, day6::part_2);
+}
+
+fn day7(c: &mut Criterion) {
+    bench_day(
+        c,
+        7,
+        10,
+        day7::parse,
+        |equations| day7::part_1(equations),
+        |equations| day7::part_2(equations),
+    );
+}
+
+criterion_group!(benches, day1, day2, day3, day4, day5, day6, day7);
+criterion_main!(benches);
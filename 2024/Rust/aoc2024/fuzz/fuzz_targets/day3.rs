@@ -0,0 +1,14 @@
+#![no_main]
+
+use aoc2024::commands::{self, day3};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // The streaming path used by the command line takes any bytes, not just UTF-8.
+    let _ = day3::run(data, &[day3::MUL, day3::DO, day3::DONT]);
+    if let Ok(contents) = std::str::from_utf8(data) {
+        for part_number in 1..=2 {
+            let _ = commands::solve(3, contents, part_number);
+        }
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use aoc2024::commands;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(contents) = std::str::from_utf8(data) {
+        for part_number in 1..=2 {
+            let _ = commands::solve(4, contents, part_number);
+        }
+    }
+});
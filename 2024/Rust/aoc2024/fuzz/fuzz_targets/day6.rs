@@ -0,0 +1,17 @@
+#![no_main]
+
+use aoc2024::commands;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Part 2 walks the whole path again for every visited cell, so keep the maps small enough
+    // for the fuzzer to stay quick.
+    if data.len() > 1024 {
+        return;
+    }
+    if let Ok(contents) = std::str::from_utf8(data) {
+        for part_number in 1..=2 {
+            let _ = commands::solve(6, contents, part_number);
+        }
+    }
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use aoc2024::commands;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(contents) = std::str::from_utf8(data) {
+        // The search is exponential in the number of factors, real inputs have at most 12.
+        if contents.lines().any(|line| line.split(' ').count() > 13) {
+            return;
+        }
+        for part_number in 1..=2 {
+            let _ = commands::solve(7, contents, part_number);
+        }
+    }
+});
@@ -0,0 +1,313 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, read_to_string};
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::commands;
+use crate::input::read_input;
+
+pub const YEAR: u16 = 2024;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub year: u16,
+    pub day: u8,
+    pub part: u8,
+    // Relative to the directory of the answers file when the input is inside it, so the file
+    // works from any working directory.
+    pub input_file: PathBuf,
+    pub answer: String,
+}
+
+impl Entry {
+    fn key(&self) -> (u16, u8, u8, &Path) {
+        (self.year, self.day, self.part, &self.input_file)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    TooHigh,
+    TooLow,
+    Wrong,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reason::TooHigh => write!(f, "too high"),
+            Reason::TooLow => write!(f, "too low"),
+            Reason::Wrong => write!(f, "wrong"),
+        }
+    }
+}
+
+// An answer the site turned down, kept so it is never submitted again.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Rejection {
+    #[serde(flatten)]
+    pub entry: Entry,
+    pub reason: Reason,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct Entries {
+    #[serde(default, rename = "answer")]
+    answers: Vec<Entry>,
+    #[serde(default, rename = "rejected", skip_serializing_if = "Vec::is_empty")]
+    rejections: Vec<Rejection>,
+}
+
+// Accepted answers kept in a TOML file as a list of `[[answer]]` tables, one per year, day, part
+// and input file, followed by the answers that were rejected as `[[rejected]]` tables.
+pub struct AnswerStore {
+    path: PathBuf,
+    directory: PathBuf,
+    entries: Vec<Entry>,
+    rejections: Vec<Rejection>,
+}
+
+impl AnswerStore {
+    // A missing file is an empty store, it is created by the first `save`.
+    pub fn load(path: &Path) -> Result<AnswerStore, io::Error> {
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => fs::canonicalize(parent)?,
+            _ => fs::canonicalize(".")?,
+        };
+        let entries = if path.exists() {
+            toml::from_str::<Entries>(&read_to_string(path)?).map_err(|error| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} is not an answers file: {}", path.display(), error),
+                )
+            })?
+        } else {
+            Entries::default()
+        };
+        Ok(AnswerStore {
+            path: path.to_path_buf(),
+            directory,
+            entries: entries.answers,
+            rejections: entries.rejections,
+        })
+    }
+
+    pub fn save(&mut self) -> Result<(), io::Error> {
+        self.entries.sort_by(|a, b| a.key().cmp(&b.key()));
+        self.rejections
+            .sort_by(|a, b| a.entry.key().cmp(&b.entry.key()));
+        let entries = Entries {
+            answers: self.entries.clone(),
+            rejections: self.rejections.clone(),
+        };
+        let contents = toml::to_string(&entries).map_err(io::Error::other)?;
+        fs::write(&self.path, contents)
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    // Where an entry's input is, relative to the working directory.
+    pub fn resolve(&self, entry: &Entry) -> PathBuf {
+        self.directory.join(&entry.input_file)
+    }
+
+    fn stored_path(&self, input_file: &Path) -> Result<PathBuf, io::Error> {
+        let input_file = fs::canonicalize(input_file)?;
+        Ok(match input_file.strip_prefix(&self.directory) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => input_file,
+        })
+    }
+
+    pub fn get(&self, day: u8, part: u8, input_file: &Path) -> Result<Option<&Entry>, io::Error> {
+        let input_file = self.stored_path(input_file)?;
+        Ok(self
+            .entries
+            .iter()
+            .find(|entry| entry.key() == (YEAR, day, part, input_file.as_path())))
+    }
+
+    // Returns the answer that was replaced, if there was a different one.
+    pub fn insert(
+        &mut self,
+        day: u8,
+        part: u8,
+        input_file: &Path,
+        answer: String,
+    ) -> Result<Option<String>, io::Error> {
+        let entry = Entry {
+            year: YEAR,
+            day,
+            part,
+            input_file: self.stored_path(input_file)?,
+            answer,
+        };
+        match self
+            .entries
+            .iter_mut()
+            .find(|existing| existing.key() == entry.key())
+        {
+            Some(existing) => {
+                let previous = std::mem::replace(existing, entry);
+                Ok((previous.answer != existing.answer).then_some(previous.answer))
+            }
+            None => {
+                self.entries.push(entry);
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn reject(
+        &mut self,
+        day: u8,
+        part: u8,
+        input_file: &Path,
+        answer: String,
+        reason: Reason,
+    ) -> Result<(), io::Error> {
+        let entry = Entry {
+            year: YEAR,
+            day,
+            part,
+            input_file: self.stored_path(input_file)?,
+            answer,
+        };
+        if !self
+            .rejections
+            .iter()
+            .any(|rejection| rejection.entry == entry)
+        {
+            self.rejections.push(Rejection { entry, reason });
+        }
+        Ok(())
+    }
+
+    // Why the answer is known to be wrong without asking the site: either it was rejected before,
+    // or it is a number that isn't below an answer that was too high or above one that was too
+    // low.
+    pub fn known_wrong(
+        &self,
+        day: u8,
+        part: u8,
+        input_file: &Path,
+        answer: &str,
+    ) -> Result<Option<String>, io::Error> {
+        let input_file = self.stored_path(input_file)?;
+        let key = (YEAR, day, part, input_file.as_path());
+        let number = answer.parse::<i128>().ok();
+        for rejection in self.rejections.iter().filter(|r| r.entry.key() == key) {
+            let rejected = &rejection.entry.answer;
+            if rejected == answer {
+                return Ok(Some(format!(
+                    "{} was already rejected as {}",
+                    answer, rejection.reason
+                )));
+            }
+            let (Some(number), Ok(bound)) = (number, rejected.parse::<i128>()) else {
+                continue;
+            };
+            match rejection.reason {
+                Reason::TooHigh if number >= bound => {
+                    return Ok(Some(format!(
+                        "{} is not below {}, which was too high",
+                        answer, rejected
+                    )))
+                }
+                Reason::TooLow if number <= bound => {
+                    return Ok(Some(format!(
+                        "{} is not above {}, which was too low",
+                        answer, rejected
+                    )))
+                }
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+}
+
+pub fn record(
+    input_file: PathBuf,
+    part_number: u8,
+    day: u8,
+    answers: PathBuf,
+) -> Result<(), io::Error> {
+    let contents = read_input(&input_file)?;
+    let answer = commands::solve(day, &contents, part_number)?.answer;
+
+    let mut store = AnswerStore::load(&answers)?;
+    if let Some(previous) = store.insert(day, part_number, &input_file, answer.clone())? {
+        warn!(
+            "Replacing the recorded answer {} for day {} part {}",
+            previous, day, part_number
+        );
+    }
+    store.save()?;
+    println!("Recorded day {} part {}: {}", day, part_number, answer);
+    Ok(())
+}
+
+pub enum Outcome {
+    Match,
+    Mismatch(String),
+    Failed(io::Error),
+}
+
+pub fn check(store: &AnswerStore, entry: &Entry) -> Outcome {
+    match read_input(&store.resolve(entry))
+        .and_then(|contents| commands::solve(entry.day, &contents, entry.part))
+    {
+        Ok(solution) if solution.answer == entry.answer => Outcome::Match,
+        Ok(solution) => Outcome::Mismatch(solution.answer),
+        Err(error) => Outcome::Failed(error),
+    }
+}
+
+// Solves every recorded answer for this year again and lists the ones that no longer match.
+pub fn verify(answers: PathBuf) -> Result<(), io::Error> {
+    let store = AnswerStore::load(&answers)?;
+    let mut checked = 0;
+    let mut failed = 0;
+    for entry in store.entries() {
+        if entry.year != YEAR {
+            info!(
+                "Skipping day {} part {} of {}",
+                entry.day, entry.part, entry.year
+            );
+            continue;
+        }
+        checked += 1;
+        let name = format!(
+            "Day {} part {} ({})",
+            entry.day,
+            entry.part,
+            entry.input_file.display()
+        );
+        match check(&store, entry) {
+            Outcome::Match => println!("{}: ok", name),
+            Outcome::Mismatch(answer) => {
+                failed += 1;
+                println!("{}: expected {}, got {}", name, entry.answer, answer);
+            }
+            Outcome::Failed(error) => {
+                failed += 1;
+                println!("{}: failed, {}", name, error);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(io::Error::other(format!(
+            "{} of {} answers did not match",
+            failed, checked
+        )));
+    }
+    println!("All {} answers match", checked);
+    Ok(())
+}
@@ -1,25 +1,319 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+// The answers file of this repository, found from the crate's directory so the default works
+// from any working directory.
+const ANSWERS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../answers.toml");
+
+// The levels of a day 2 report may differ by this much by default.
+const MIN_STEP: i64 = 1;
+const MAX_STEP: i64 = 3;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 pub struct Cli {
+    /// The puzzle input, needed by the day, `bench` and `record` commands
     #[arg(short, long)]
-    pub input_file: std::path::PathBuf,
+    pub input_file: Option<std::path::PathBuf>,
 
     #[arg(short, long, default_value_t = 1)]
     pub part_number: u8,
 
+    /// Report how long parsing and solving took, can't be combined with day specific options
+    #[arg(long)]
+    pub time: bool,
+
+    /// `json` writes one line per day and part with the answer, timings, input hash and any error,
+    /// and can't be combined with day specific options
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Log more to stderr, repeat for more detail: info, debug and then trace
+    #[arg(short, long, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// Only log errors
+    #[arg(short, long)]
+    pub quiet: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
-    Day1,
-    Day2,
-    Day3,
-    Day4,
-    Day5,
+    Day1 {
+        /// Skip and report lines that are not a pair of IDs instead of failing
+        #[arg(short, long)]
+        lenient: bool,
+
+        #[command(subcommand)]
+        mode: Option<Day1Mode>,
+    },
+    Day2 {
+        /// List every unsafe report along with the reason it fails
+        #[arg(short, long)]
+        explain: bool,
+
+        /// Smallest allowed difference between neighbouring levels
+        #[arg(long, default_value_t = MIN_STEP)]
+        min_step: i64,
+
+        /// Largest allowed difference between neighbouring levels
+        #[arg(long, default_value_t = MAX_STEP)]
+        max_step: i64,
+
+        /// How many levels may be removed from a report, defaults to 0 for part 1 and 1 for part 2
+        #[arg(short, long)]
+        tolerance: Option<usize>,
+    },
+    Day3 {
+        /// List every recognised instruction along with a summary of the totals
+        #[arg(short, long, value_enum, num_args = 0..=1, default_missing_value = "text")]
+        trace: Option<TraceFormat>,
+    },
+    Day4 {
+        /// Count every occurrence of this word in all eight directions, can be repeated
+        #[arg(short, long = "word")]
+        words: Vec<String>,
+
+        /// Count every rotation and reflection of a 2D template such as `M.S/.A./M.S`, where `.`
+        /// matches any letter, or one of the built in `x-mas` and `plus` templates
+        #[arg(short, long = "template")]
+        templates: Vec<String>,
+
+        /// Print the grid with only the letters that are part of a match
+        #[arg(short, long)]
+        show: bool,
+
+        /// List the start and direction of every match
+        #[arg(short, long)]
+        list: bool,
+    },
+    Day5 {
+        /// Explain which rules each invalid update breaks and how it gets reordered
+        #[arg(short, long)]
+        explain: bool,
+
+        #[command(subcommand)]
+        mode: Option<Day5Mode>,
+    },
     Day6,
     Day7,
+    /// Solve a day repeatedly and report statistics on how long parsing and solving take
+    Bench {
+        day: u8,
+
+        /// How many timed runs to make, after one untimed warm up run
+        #[arg(short, long, default_value_t = 100)]
+        runs: usize,
+
+        /// Save the results to this JSON baseline, keeping any results for other days and parts
+        #[arg(short, long)]
+        save: Option<std::path::PathBuf>,
+
+        /// Compare the results against this JSON baseline
+        #[arg(short, long)]
+        baseline: Option<std::path::PathBuf>,
+
+        /// Percentage the median total time may grow over the baseline before it is a regression
+        #[arg(short, long, default_value_t = 10.0)]
+        threshold: f64,
+    },
+    /// Solve a day and store the answer as the accepted one for this input and part
+    Record {
+        day: u8,
+
+        /// The answers file, created if it does not exist
+        #[arg(short, long, default_value = ANSWERS)]
+        answers: std::path::PathBuf,
+    },
+    /// Solve every recorded answer again and report the ones that no longer match
+    Verify {
+        #[arg(short, long, default_value = ANSWERS)]
+        answers: std::path::PathBuf,
+    },
+    /// Download a day's input into the cache, unless it is already there, and print its path
+    Fetch {
+        day: u8,
+
+        #[command(flatten)]
+        site: SiteArgs,
+    },
+    /// Solve a day and submit the answer, unless it is already known to be right or wrong. Uses
+    /// the fetched input when no --input-file is given
+    Submit {
+        day: u8,
+
+        /// Where accepted and rejected answers are kept
+        #[arg(short, long, default_value = ANSWERS)]
+        answers: std::path::PathBuf,
+
+        #[command(flatten)]
+        site: SiteArgs,
+    },
+    /// Report the standings and star times of a private leaderboard
+    Leaderboard {
+        /// A JSON export of the leaderboard, instead of downloading it
+        #[arg(long, conflicts_with = "id")]
+        file: Option<std::path::PathBuf>,
+
+        /// The ID of the private leaderboard to download, the number at the end of its URL
+        #[arg(long)]
+        id: Option<u64>,
+
+        #[arg(short, long, value_enum, default_value_t = LeaderboardFormat::Table)]
+        format: LeaderboardFormat,
+
+        #[command(flatten)]
+        site: SiteArgs,
+    },
+    /// Write a random puzzle input for a day
+    Generate {
+        day: u8,
+
+        /// The same seed and size always give the same input
+        #[arg(short, long, default_value_t = 0)]
+        seed: u64,
+
+        /// Lines for days 1, 2 and 7, instructions for day 3, updates for day 5 and the side of
+        /// the grid for days 4 and 6, defaults to about the size of a real input
+        #[arg(short = 'n', long)]
+        size: Option<usize>,
+
+        /// Write the input to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+
+        /// Also print the expected answers to stderr, for the parts the generator knows them for
+        #[arg(short, long)]
+        answers: bool,
+    },
+}
+
+impl Commands {
+    pub fn day(&self) -> Option<u8> {
+        match self {
+            Commands::Day1 { .. } => Some(1),
+            Commands::Day2 { .. } => Some(2),
+            Commands::Day3 { .. } => Some(3),
+            Commands::Day4 { .. } => Some(4),
+            Commands::Day5 { .. } => Some(5),
+            Commands::Day6 => Some(6),
+            Commands::Day7 => Some(7),
+            Commands::Bench { .. }
+            | Commands::Record { .. }
+            | Commands::Verify { .. }
+            | Commands::Fetch { .. }
+            | Commands::Submit { .. }
+            | Commands::Leaderboard { .. }
+            | Commands::Generate { .. } => None,
+        }
+    }
+
+    // Whether a day command was given any option that changes what it does, which only its own
+    // handler knows how to apply.
+    pub fn has_day_options(&self) -> bool {
+        match self {
+            Commands::Day1 { lenient, mode } => *lenient || mode.is_some(),
+            Commands::Day2 {
+                explain,
+                min_step,
+                max_step,
+                tolerance,
+            } => *explain || *min_step != MIN_STEP || *max_step != MAX_STEP || tolerance.is_some(),
+            Commands::Day3 { trace } => trace.is_some(),
+            Commands::Day4 {
+                words,
+                templates,
+                show,
+                list,
+            } => !words.is_empty() || !templates.is_empty() || *show || *list,
+            Commands::Day5 { explain, mode } => *explain || mode.is_some(),
+            _ => false,
+        }
+    }
+}
+
+// How to reach the Advent of Code site, the session token comes from the config or AOC_SESSION.
+#[derive(Args, Debug)]
+pub struct SiteArgs {
+    /// A TOML file with the session token, base URL, contact for the User-Agent, cache directory
+    /// and request interval, all optional
+    #[arg(short, long, default_value = "aoc.toml")]
+    pub config: std::path::PathBuf,
+
+    /// Overrides the config and AOC_BASE_URL, for example to use a local server
+    #[arg(long)]
+    pub base_url: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Day1Mode {
+    /// Report the pairing, the largest distances, the overlap between the lists and statistics
+    Analyze {
+        #[arg(short, long, value_enum, default_value_t = AnalysisFormat::Table)]
+        format: AnalysisFormat,
+
+        /// How many of the largest distances to list
+        #[arg(short, long, default_value_t = 10)]
+        top: usize,
+
+        /// Only write this table, needed for CSV so the output is a single table
+        #[arg(long, value_enum)]
+        table: Option<AnalysisTable>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum AnalysisTable {
+    Summary,
+    Statistics,
+    Distances,
+    Overlap,
+    Pairs,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum AnalysisFormat {
+    Table,
+    Csv,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Day5Mode {
+    /// Export the page-ordering rules as a graph along with an analysis of them
+    Graph {
+        #[arg(short, long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+
+        /// Highlight the rules relevant to the update at this (0-based) index
+        #[arg(short, long)]
+        update: Option<usize>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum TraceFormat {
+    Text,
+    Csv,
+    /// One object per line for every instruction, then one with the summary
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum LeaderboardFormat {
+    Table,
+    Markdown,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum GraphFormat {
+    Dot,
+    Json,
 }
@@ -0,0 +1,212 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, read_to_string};
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::commands;
+use crate::input::read_input;
+
+// Times are kept in nanoseconds so baselines stay readable and easy to compare.
+#[derive(Serialize, Deserialize)]
+struct Statistics {
+    min: f64,
+    median: f64,
+    mean: f64,
+    std_dev: f64,
+}
+
+impl Statistics {
+    fn new(durations: &[Duration]) -> Statistics {
+        let mut nanoseconds: Vec<f64> = durations.iter().map(|d| d.as_nanos() as f64).collect();
+        nanoseconds.sort_by(f64::total_cmp);
+        let count = nanoseconds.len();
+        let middle = count / 2;
+        let median = if count.is_multiple_of(2) {
+            (nanoseconds[middle - 1] + nanoseconds[middle]) / 2.0
+        } else {
+            nanoseconds[middle]
+        };
+        let mean = nanoseconds.iter().sum::<f64>() / count as f64;
+        // The sample standard deviation, a single run has none.
+        let std_dev = if count > 1 {
+            let squares: f64 = nanoseconds.iter().map(|n| (n - mean).powi(2)).sum();
+            (squares / (count - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+        Statistics {
+            min: nanoseconds[0],
+            median,
+            mean,
+            std_dev,
+        }
+    }
+}
+
+fn format_nanoseconds(nanoseconds: f64) -> String {
+    format!("{:.3?}", Duration::from_nanos(nanoseconds.round() as u64))
+}
+
+#[derive(Serialize, Deserialize)]
+struct BenchResult {
+    day: u8,
+    part: u8,
+    input_file: PathBuf,
+    runs: usize,
+    answer: String,
+    parse: Statistics,
+    solve: Statistics,
+    total: Statistics,
+}
+
+impl BenchResult {
+    fn key(&self) -> String {
+        format!("day{}-part{}", self.day, self.part)
+    }
+
+    fn stages(&self) -> [(&'static str, &Statistics); 3] {
+        [
+            ("parse", &self.parse),
+            ("solve", &self.solve),
+            ("total", &self.total),
+        ]
+    }
+
+    fn print(&self) {
+        println!(
+            "Day {} part {}, {} runs, answer {}",
+            self.day, self.part, self.runs, self.answer
+        );
+        println!(
+            "{:<6} {:>12} {:>12} {:>12} {:>12}",
+            "", "min", "median", "mean", "std dev"
+        );
+        for (name, statistics) in self.stages() {
+            println!(
+                "{:<6} {:>12} {:>12} {:>12} {:>12}",
+                name,
+                format_nanoseconds(statistics.min),
+                format_nanoseconds(statistics.median),
+                format_nanoseconds(statistics.mean),
+                format_nanoseconds(statistics.std_dev)
+            );
+        }
+    }
+
+    // Prints how the medians moved and returns the change in the median total time as a percentage.
+    fn compare(&self, baseline: &BenchResult) -> f64 {
+        let change = |statistics: &Statistics, base: &Statistics| {
+            100.0 * (statistics.median - base.median) / base.median.max(1.0)
+        };
+        println!("Compared to the baseline medians:");
+        for ((name, statistics), (_, base)) in self.stages().into_iter().zip(baseline.stages()) {
+            println!(
+                "{:<6} {:>12} -> {:>12} {:>+8.1}%",
+                name,
+                format_nanoseconds(base.median),
+                format_nanoseconds(statistics.median),
+                change(statistics, base)
+            );
+        }
+        if baseline.answer != self.answer {
+            println!(
+                "The answer changed from {} to {}",
+                baseline.answer, self.answer
+            );
+        }
+        change(&self.total, &baseline.total)
+    }
+}
+
+type Baseline = BTreeMap<String, BenchResult>;
+
+fn read_baseline(path: &PathBuf) -> Result<Baseline, io::Error> {
+    let contents = read_to_string(path)?;
+    serde_json::from_str(&contents).map_err(|error| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not a benchmark baseline: {}", path.display(), error),
+        )
+    })
+}
+
+pub fn handle(
+    input_file: PathBuf,
+    part_number: u8,
+    day: u8,
+    runs: usize,
+    save: Option<PathBuf>,
+    baseline: Option<PathBuf>,
+    threshold: f64,
+) -> Result<(), io::Error> {
+    if runs == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "At least one run is needed",
+        ));
+    }
+    let contents = read_input(&input_file)?;
+
+    let answer = commands::solve(day, &contents, part_number)?.answer;
+    let mut parse_times = Vec::with_capacity(runs);
+    let mut solve_times = Vec::with_capacity(runs);
+    let mut total_times = Vec::with_capacity(runs);
+    for _ in 0..runs {
+        let solution = commands::solve(day, &contents, part_number)?;
+        parse_times.push(solution.parse_time);
+        solve_times.push(solution.solve_time);
+        total_times.push(solution.parse_time + solution.solve_time);
+    }
+
+    let result = BenchResult {
+        day,
+        part: part_number,
+        input_file,
+        runs,
+        answer,
+        parse: Statistics::new(&parse_times),
+        solve: Statistics::new(&solve_times),
+        total: Statistics::new(&total_times),
+    };
+    result.print();
+
+    let mut regression = None;
+    if let Some(path) = baseline {
+        match read_baseline(&path)?.get(&result.key()) {
+            Some(base) => {
+                println!();
+                let change = result.compare(base);
+                if change > threshold {
+                    regression = Some(change);
+                }
+            }
+            None => warn!(
+                "{} has no result for day {} part {}",
+                path.display(),
+                day,
+                part_number
+            ),
+        }
+    }
+
+    if let Some(path) = save {
+        let mut saved = if path.exists() {
+            read_baseline(&path)?
+        } else {
+            Baseline::new()
+        };
+        saved.insert(result.key(), result);
+        fs::write(&path, serde_json::to_string_pretty(&saved)? + "\n")?;
+    }
+
+    match regression {
+        Some(change) => Err(io::Error::other(format!(
+            "Regression: the median total time grew by {:.1}%, above the {}% threshold",
+            change, threshold
+        ))),
+        None => Ok(()),
+    }
+}
@@ -1,78 +1,387 @@
-use nom::{
-    character::complete::{digit1, newline, space1},
-    multi::separated_list1,
-    sequence::separated_pair,
-    IResult,
-};
-use std::collections::HashMap;
-use std::fs::read_to_string;
+use nom::{character::complete::space1, sequence::separated_pair};
+use std::collections::{BTreeMap, HashMap};
 use std::io;
+use std::iter::zip;
+use tracing::{instrument, warn};
+
+use crate::args::{AnalysisFormat, AnalysisTable, Day1Mode};
+use crate::input::read_input;
+use crate::parsing::{parse_all, unsigned, ParseResult};
 
 #[derive(Debug, PartialEq)]
 pub struct IDPair {
-    pub first: u32,
-    pub second: u32,
+    pub first: u64,
+    pub second: u64,
 }
 
-fn parse_id_pair(input: &str) -> IResult<&str, IDPair> {
-    let (input, (first, second)) = separated_pair(digit1, space1, digit1)(input)?;
-    Ok((
-        input,
-        IDPair {
-            first: first.parse().unwrap(),
-            second: second.parse().unwrap(),
-        },
-    ))
+fn parse_id_pair(input: &str) -> ParseResult<'_, IDPair> {
+    let (input, (first, second)) = separated_pair(unsigned, space1, unsigned)(input)?;
+    Ok((input, IDPair { first, second }))
 }
 
-fn parse_id_pairs(input: &str) -> IResult<&str, Vec<IDPair>> {
-    separated_list1(newline, parse_id_pair)(input)
+// In lenient mode lines that don't parse are logged as warnings and skipped, otherwise the first
+// one is an error.
+fn parse_id_pairs(input: &str, lenient: bool) -> Result<Vec<IDPair>, io::Error> {
+    let mut id_pairs = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match parse_all(line, "a pair of IDs", parse_id_pair) {
+            Ok(id_pair) => id_pairs.push(id_pair),
+            Err(_) if lenient => warn!("Skipping line {}: {:?}", index + 1, line),
+            Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Line {} is not a pair of IDs: {:?}", index + 1, line),
+                ))
+            }
+        }
+    }
+    Ok(id_pairs)
 }
 
-pub fn handle(input_file: std::path::PathBuf, part_number: u8) -> Result<(), io::Error> {
-    let contents = read_to_string(input_file)?;
+fn check_same_length(left_ids: &[u64], right_ids: &[u64]) -> Result<(), io::Error> {
+    if left_ids.len() != right_ids.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "The lists have different lengths, {} left IDs and {} right IDs",
+                left_ids.len(),
+                right_ids.len()
+            ),
+        ));
+    }
+    Ok(())
+}
 
-    let id_pairs = parse_id_pairs(&contents);
-    let mut left_ids: Vec<u32> = Vec::new();
-    let mut right_ids: Vec<u32> = Vec::new();
-    for id_pair in id_pairs.unwrap().1 {
+// The two lists of location IDs, in the order they appear in the input.
+pub struct IDLists {
+    pub left_ids: Vec<u64>,
+    pub right_ids: Vec<u64>,
+}
+
+#[instrument(name = "parse", skip_all)]
+pub fn parse_id_lists(contents: &str, lenient: bool) -> Result<IDLists, io::Error> {
+    let id_pairs = parse_id_pairs(contents, lenient)?;
+    let mut left_ids: Vec<u64> = Vec::new();
+    let mut right_ids: Vec<u64> = Vec::new();
+    for id_pair in id_pairs {
         left_ids.push(id_pair.first);
         right_ids.push(id_pair.second);
     }
+    Ok(IDLists {
+        left_ids,
+        right_ids,
+    })
+}
 
-    match part_number {
-        1 => part_1(&mut left_ids, &mut right_ids),
-        2 => part_2(left_ids, right_ids),
-        _ => Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            "Invalid part number",
-        )),
+pub fn parse(contents: &str) -> Result<IDLists, io::Error> {
+    parse_id_lists(contents, false)
+}
+
+pub fn handle(
+    input_file: std::path::PathBuf,
+    part_number: u8,
+    lenient: bool,
+    mode: Option<Day1Mode>,
+) -> Result<(), io::Error> {
+    let contents = read_input(&input_file)?;
+    let mut id_lists = parse_id_lists(&contents, lenient)?;
+
+    if let Some(Day1Mode::Analyze { format, top, table }) = mode {
+        print!(
+            "{}",
+            analyze(
+                &mut id_lists.left_ids,
+                &mut id_lists.right_ids,
+                format,
+                top,
+                table
+            )?
+        );
+        return Ok(());
     }
+
+    let answer = match part_number {
+        1 => part_1(&id_lists)?,
+        2 => part_2(&id_lists)?,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid part number",
+            ))
+        }
+    };
+    println!("{}", answer);
+    Ok(())
 }
 
-pub fn part_1(left_ids: &mut [u32], right_ids: &mut [u32]) -> Result<(), io::Error> {
+#[instrument(skip_all)]
+pub fn part_1(id_lists: &IDLists) -> Result<u128, io::Error> {
+    check_same_length(&id_lists.left_ids, &id_lists.right_ids)?;
+    let mut left_ids = id_lists.left_ids.clone();
+    let mut right_ids = id_lists.right_ids.clone();
     left_ids.sort();
     right_ids.sort();
-    let mut diff: u32 = 0;
-    for i in 0..left_ids.len() {
-        diff += (left_ids[i] as i32 - right_ids[i] as i32).unsigned_abs();
+    let mut diff: u128 = 0;
+    for (left_id, right_id) in zip(left_ids.iter(), right_ids.iter()) {
+        diff += left_id.abs_diff(*right_id) as u128;
     }
-    println!("{}", diff);
-    Ok(())
+    Ok(diff)
 }
 
-pub fn part_2(left_ids: Vec<u32>, right_ids: Vec<u32>) -> Result<(), io::Error> {
-    let mut right_counts: HashMap<u32, u32> = HashMap::new();
-    for right_id in right_ids {
+#[instrument(skip_all)]
+pub fn part_2(id_lists: &IDLists) -> Result<u128, io::Error> {
+    let mut right_counts: HashMap<u64, u64> = HashMap::new();
+    for &right_id in &id_lists.right_ids {
         *right_counts.entry(right_id).or_insert(0) += 1;
     }
 
-    let mut count_result: u32 = 0;
-    for left_id in left_ids {
+    let mut count_result: u128 = 0;
+    for &left_id in &id_lists.left_ids {
         if right_counts.contains_key(&left_id) {
-            count_result += left_id * right_counts[&left_id];
+            count_result += left_id as u128 * right_counts[&left_id] as u128;
         }
     }
-    println!("{}", count_result);
-    Ok(())
+    Ok(count_result)
+}
+
+struct Statistics {
+    min: u64,
+    max: u64,
+    // Twice the median so it stays exact for wide IDs when it falls between two of them.
+    double_median: u128,
+    duplicates: usize,
+}
+
+impl Statistics {
+    // Expects `sorted_ids` to be sorted and non-empty.
+    fn new(sorted_ids: &[u64]) -> Statistics {
+        let middle = sorted_ids.len() / 2;
+        let double_median = if sorted_ids.len().is_multiple_of(2) {
+            sorted_ids[middle - 1] as u128 + sorted_ids[middle] as u128
+        } else {
+            sorted_ids[middle] as u128 * 2
+        };
+        let distinct = sorted_ids
+            .iter()
+            .zip(sorted_ids.iter().skip(1))
+            .filter(|(a, b)| a != b)
+            .count()
+            + 1;
+        Statistics {
+            min: sorted_ids[0],
+            max: sorted_ids[sorted_ids.len() - 1],
+            double_median,
+            duplicates: sorted_ids.len() - distinct,
+        }
+    }
+
+    fn median(&self) -> String {
+        if self.double_median.is_multiple_of(2) {
+            (self.double_median / 2).to_string()
+        } else {
+            format!("{}.5", self.double_median / 2)
+        }
+    }
+}
+
+struct Table {
+    kind: AnalysisTable,
+    title: &'static str,
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn render(&self, format: AnalysisFormat) -> String {
+        let mut output = String::new();
+        match format {
+            AnalysisFormat::Table => {
+                let widths: Vec<usize> = (0..self.headers.len())
+                    .map(|column| {
+                        self.rows
+                            .iter()
+                            .map(|row| row[column].len())
+                            .chain([self.headers[column].len()])
+                            .max()
+                            .unwrap_or(0)
+                    })
+                    .collect();
+                let line = |cells: Vec<&str>| {
+                    cells
+                        .iter()
+                        .zip(&widths)
+                        .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+                        .collect::<Vec<String>>()
+                        .join("  ")
+                        + "\n"
+                };
+                output.push_str(self.title);
+                output.push('\n');
+                output.push_str(&line(self.headers.clone()));
+                for row in &self.rows {
+                    output.push_str(&line(row.iter().map(|cell| cell.as_str()).collect()));
+                }
+            }
+            AnalysisFormat::Csv => {
+                output.push_str(&self.headers.join(","));
+                output.push('\n');
+                for row in &self.rows {
+                    output.push_str(&row.join(","));
+                    output.push('\n');
+                }
+            }
+        }
+        output
+    }
+}
+
+// Tables are separated by a blank line. A CSV file can only hold one table, so for CSV one has to
+// be chosen.
+pub fn analyze(
+    left_ids: &mut [u64],
+    right_ids: &mut [u64],
+    format: AnalysisFormat,
+    top: usize,
+    table: Option<AnalysisTable>,
+) -> Result<String, io::Error> {
+    if format == AnalysisFormat::Csv && table.is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "CSV output needs a --table to write",
+        ));
+    }
+    check_same_length(left_ids, right_ids)?;
+    if left_ids.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "There are no IDs to analyze",
+        ));
+    }
+    left_ids.sort();
+    right_ids.sort();
+
+    let distances: Vec<u64> = zip(left_ids.iter(), right_ids.iter())
+        .map(|(left, right)| left.abs_diff(*right))
+        .collect();
+    let pairs = Table {
+        kind: AnalysisTable::Pairs,
+        title: "Pairs",
+        headers: vec!["index", "left", "right", "distance"],
+        rows: (0..distances.len())
+            .map(|i| {
+                vec![
+                    i.to_string(),
+                    left_ids[i].to_string(),
+                    right_ids[i].to_string(),
+                    distances[i].to_string(),
+                ]
+            })
+            .collect(),
+    };
+
+    let mut by_distance: Vec<usize> = (0..distances.len()).collect();
+    by_distance.sort_by(|a, b| distances[*b].cmp(&distances[*a]).then(a.cmp(b)));
+    let total_distance: u128 = distances.iter().map(|&distance| distance as u128).sum();
+    let largest = Table {
+        kind: AnalysisTable::Distances,
+        title: "Largest distances",
+        headers: vec!["index", "left", "right", "distance", "share"],
+        rows: by_distance
+            .iter()
+            .take(top)
+            .map(|&i| {
+                vec![
+                    i.to_string(),
+                    left_ids[i].to_string(),
+                    right_ids[i].to_string(),
+                    distances[i].to_string(),
+                    format!(
+                        "{:.2}%",
+                        100.0 * distances[i] as f64 / total_distance.max(1) as f64
+                    ),
+                ]
+            })
+            .collect(),
+    };
+
+    let mut counts: BTreeMap<u64, (u64, u64)> = BTreeMap::new();
+    for &left_id in left_ids.iter() {
+        counts.entry(left_id).or_default().0 += 1;
+    }
+    for &right_id in right_ids.iter() {
+        counts.entry(right_id).or_default().1 += 1;
+    }
+    let overlapping: Vec<(u64, u64, u64, u128)> = counts
+        .iter()
+        .filter(|(_, (left_count, right_count))| *left_count > 0 && *right_count > 0)
+        .map(|(&id, &(left_count, right_count))| {
+            (
+                id,
+                left_count,
+                right_count,
+                id as u128 * left_count as u128 * right_count as u128,
+            )
+        })
+        .collect();
+    let similarity_score: u128 = overlapping.iter().map(|overlap| overlap.3).sum();
+    let overlap = Table {
+        kind: AnalysisTable::Overlap,
+        title: "Overlap",
+        headers: vec!["id", "left count", "right count", "similarity"],
+        rows: overlapping
+            .iter()
+            .map(|(id, left_count, right_count, similarity)| {
+                vec![
+                    id.to_string(),
+                    left_count.to_string(),
+                    right_count.to_string(),
+                    similarity.to_string(),
+                ]
+            })
+            .collect(),
+    };
+
+    let left_statistics = Statistics::new(left_ids);
+    let right_statistics = Statistics::new(right_ids);
+    let statistic_row = |name: &str, statistic: fn(&Statistics) -> String| {
+        vec![
+            name.to_string(),
+            statistic(&left_statistics),
+            statistic(&right_statistics),
+        ]
+    };
+    let statistics = Table {
+        kind: AnalysisTable::Statistics,
+        title: "Statistics",
+        headers: vec!["statistic", "left", "right"],
+        rows: vec![
+            statistic_row("min", |s| s.min.to_string()),
+            statistic_row("max", |s| s.max.to_string()),
+            statistic_row("median", |s| s.median()),
+            statistic_row("duplicates", |s| s.duplicates.to_string()),
+        ],
+    };
+
+    let summary = Table {
+        kind: AnalysisTable::Summary,
+        title: "Summary",
+        headers: vec!["summary", "value"],
+        rows: vec![
+            vec!["pairs".to_string(), distances.len().to_string()],
+            vec!["total distance".to_string(), total_distance.to_string()],
+            vec!["overlapping ids".to_string(), overlapping.len().to_string()],
+            vec!["similarity score".to_string(), similarity_score.to_string()],
+        ],
+    };
+
+    Ok([summary, statistics, largest, overlap, pairs]
+        .iter()
+        .filter(|candidate| table.is_none_or(|kind| kind == candidate.kind))
+        .map(|candidate| candidate.render(format))
+        .collect::<Vec<String>>()
+        .join("\n"))
 }
@@ -1,112 +1,306 @@
-use nom::{
-    character::complete::{digit1, newline, space1},
-    combinator::map_res,
-    multi::separated_list1,
-    IResult,
-};
+use std::fmt;
 use std::io;
-use std::{fs::read_to_string, iter::zip};
+use std::ops::RangeInclusive;
+use tracing::instrument;
+
+use crate::input::read_input;
+use crate::parsing::{lines, parse_all, signed, whitespace_separated, ParseResult};
 
 #[derive(Debug, PartialEq)]
 pub struct Report {
     pub levels: Vec<i32>,
 }
 
-fn str_to_u32(input: &str) -> IResult<&str, i32> {
-    map_res(digit1, str::parse)(input)
+fn parse_report(input: &str) -> ParseResult<'_, Report> {
+    let (input, levels) = whitespace_separated(signed)(input)?;
+    Ok((input, Report { levels }))
 }
 
-fn parse_report(input: &str) -> IResult<&str, Report> {
-    let (input, levels) = separated_list1(space1, str_to_u32)(input)?;
-    Ok((input, Report { levels }))
+pub fn handle(
+    input_file: std::path::PathBuf,
+    part_number: u8,
+    explain: bool,
+    min_step: i64,
+    max_step: i64,
+    tolerance: Option<usize>,
+) -> Result<(), io::Error> {
+    let contents = read_input(&input_file)?;
+    // Part 2 is the same check with the Problem Dampener allowed to remove a single level.
+    let default_tolerance = match part_number {
+        1 => 0,
+        2 => 1,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid part number",
+            ))
+        }
+    };
+    let rules = SafetyRules::new(min_step, max_step, tolerance.unwrap_or(default_tolerance))?;
+    let reports = parse(&contents)?;
+    if explain {
+        explain_reports(&reports, &rules);
+    }
+    println!("{}", count_safe_reports(&reports, &rules));
+    Ok(())
 }
 
-fn parse_reports(input: &str) -> IResult<&str, Vec<Report>> {
-    let (input, reports) = separated_list1(newline, parse_report)(input)?;
-    Ok((input, reports))
+#[instrument(skip_all)]
+pub fn parse(contents: &str) -> Result<Vec<Report>, io::Error> {
+    parse_all(contents, "a list of reports", lines(parse_report))
 }
 
-pub fn handle(input_file: std::path::PathBuf, part_number: u8) -> Result<(), io::Error> {
-    let contents = read_to_string(input_file)?;
-    match part_number {
-        1 => part_1(&contents),
-        2 => part_2(&contents),
-        _ => Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            "Invalid part number",
-        )),
-    }
+#[instrument(skip_all)]
+pub fn part_1(reports: &[Report]) -> Result<usize, io::Error> {
+    Ok(count_safe_reports(reports, &SafetyRules::new(1, 3, 0)?))
+}
+
+#[instrument(skip_all)]
+pub fn part_2(reports: &[Report]) -> Result<usize, io::Error> {
+    Ok(count_safe_reports(reports, &SafetyRules::new(1, 3, 1)?))
 }
 
-trait LevelsValidation {
-    fn levels_are_valid(&self) -> bool;
-    fn problem_dampened_is_valid(&self) -> bool;
+pub struct SafetyRules {
+    pub step_range: RangeInclusive<i64>,
+    // How many levels the Problem Dampener may remove from a report.
+    pub tolerance: usize,
 }
 
-impl LevelsValidation for Report {
-    fn levels_are_valid(&self) -> bool {
-        if self.levels.len() < 2 {
-            return true;
+impl SafetyRules {
+    pub fn new(min_step: i64, max_step: i64, tolerance: usize) -> Result<SafetyRules, io::Error> {
+        if min_step < 0 || min_step > max_step {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid step range {}..={}", min_step, max_step),
+            ));
+        }
+        Ok(SafetyRules {
+            step_range: min_step..=max_step,
+            tolerance,
+        })
+    }
+
+    fn step(&self, level: i32, next_level: i32, direction: Direction) -> i64 {
+        let change = next_level as i64 - level as i64;
+        match direction {
+            Direction::Increasing => change,
+            Direction::Decreasing => -change,
         }
+    }
+
+    fn allows(&self, level: i32, next_level: i32, direction: Direction) -> bool {
+        self.step_range
+            .contains(&self.step(level, next_level, direction))
+    }
+}
 
-        let first = self.levels[0];
-        let second = self.levels[1];
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Direction {
+    Increasing,
+    Decreasing,
+}
 
-        let to_check: &[i32] = if first < second {
-            &self.levels[..]
-        } else {
-            let mut reversed = self.levels.clone();
-            reversed.reverse();
-            Box::leak(reversed.into_boxed_slice())
+// The first pair of (not removed) neighbouring levels that breaks the rules.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Violation {
+    pub index: usize,
+    pub next_index: usize,
+    pub level: i32,
+    pub next_level: i32,
+    pub direction: Direction,
+    pub min_step: i64,
+    pub max_step: i64,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let change = self.next_level as i64 - self.level as i64;
+        let step = match self.direction {
+            Direction::Increasing => change,
+            Direction::Decreasing => -change,
+        };
+        let direction = match self.direction {
+            Direction::Increasing => "increasing",
+            Direction::Decreasing => "decreasing",
         };
+        write!(
+            f,
+            "levels {} and {} ({} -> {}) ",
+            self.index, self.next_index, self.level, self.next_level
+        )?;
+        if change == 0 {
+            write!(f, "do not change")
+        } else if step < 0 {
+            write!(f, "stop {}", direction)
+        } else if step < self.min_step {
+            write!(f, "change by {}, less than {}", step, self.min_step)
+        } else {
+            write!(f, "change by {}, more than {}", step, self.max_step)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Safety {
+    Safe,
+    SafeWithoutLevels(Vec<usize>),
+    Unsafe(Violation),
+}
 
-        for (current, next) in zip(to_check, &to_check[1..]) {
-            let diff = next - current;
-            if !(1..=3).contains(&diff) {
-                return false;
+fn first_violation(
+    levels: &[i32],
+    skipped_index: Option<usize>,
+    direction: Direction,
+    rules: &SafetyRules,
+) -> Option<Violation> {
+    let mut previous: Option<(usize, i32)> = None;
+    for (index, &level) in levels.iter().enumerate() {
+        if Some(index) == skipped_index {
+            continue;
+        }
+        if let Some((previous_index, previous_level)) = previous {
+            if !rules.allows(previous_level, level, direction) {
+                return Some(Violation {
+                    index: previous_index,
+                    next_index: index,
+                    level: previous_level,
+                    next_level: level,
+                    direction,
+                    min_step: *rules.step_range.start(),
+                    max_step: *rules.step_range.end(),
+                });
             }
         }
+        previous = Some((index, level));
+    }
+    None
+}
 
-        true
+// Finds the fewest levels to remove so that the rest are safe in `direction`, if that is no more
+// than the tolerance. `removals[j]` is the fewest removals among the first `j + 1` levels when
+// level `j` is kept, and since at most `tolerance` levels can be skipped between two kept levels
+// only that many predecessors need to be looked at, making this O(n * k).
+fn fewest_removals(
+    levels: &[i32],
+    direction: Direction,
+    rules: &SafetyRules,
+) -> Option<Vec<usize>> {
+    let tolerance = rules.tolerance;
+    if levels.len() <= tolerance {
+        return Some((0..levels.len()).collect());
     }
 
-    fn problem_dampened_is_valid(&self) -> bool {
-        if self.levels.len() < 2 {
-            return true;
+    let mut removals: Vec<usize> = (0..levels.len()).collect();
+    let mut previous_kept: Vec<Option<usize>> = vec![None; levels.len()];
+    for next_index in 1..levels.len() {
+        for index in next_index.saturating_sub(tolerance + 1)..next_index {
+            let candidate = removals[index] + (next_index - index - 1);
+            if candidate < removals[next_index]
+                && rules.allows(levels[index], levels[next_index], direction)
+            {
+                removals[next_index] = candidate;
+                previous_kept[next_index] = Some(index);
+            }
         }
+    }
 
-        if self.levels_are_valid() {
-            return true;
-        }
+    let (last_kept, total_removals) = (levels.len() - 1 - tolerance..levels.len())
+        .map(|index| (index, removals[index] + levels.len() - 1 - index))
+        .min_by_key(|&(_, total_removals)| total_removals)?;
+    if total_removals > tolerance {
+        return None;
+    }
+
+    let mut is_kept = vec![false; levels.len()];
+    let mut kept = Some(last_kept);
+    while let Some(index) = kept {
+        is_kept[index] = true;
+        kept = previous_kept[index];
+    }
+    Some((0..levels.len()).filter(|&index| !is_kept[index]).collect())
+}
+
+impl Report {
+    // Whether the report is safe as it is, which levels to remove to make it safe, or the first
+    // pair of levels that makes it unsafe in the direction it starts out in.
+    pub fn safety(&self, rules: &SafetyRules) -> Safety {
+        let levels = &self.levels[..];
+        let increasing = first_violation(levels, None, Direction::Increasing, rules);
+        let decreasing = first_violation(levels, None, Direction::Decreasing, rules);
+        let (increasing, decreasing) = match (increasing, decreasing) {
+            (Some(increasing), Some(decreasing)) => (increasing, decreasing),
+            _ => return Safety::Safe,
+        };
 
-        for i in 0..self.levels.len() {
-            let mut test_levels = self.levels.clone();
-            test_levels.remove(i);
-            let report = Report {
-                levels: test_levels,
-            };
-            if report.levels_are_valid() {
-                return true;
+        match rules.tolerance {
+            0 => {}
+            1 => {
+                // Removing any level other than the two in the first violation would leave that
+                // pair next to each other, so those are the only two candidates in each direction.
+                for violation in [increasing, decreasing] {
+                    for index in [violation.index, violation.next_index] {
+                        if first_violation(levels, Some(index), violation.direction, rules)
+                            .is_none()
+                        {
+                            return Safety::SafeWithoutLevels(vec![index]);
+                        }
+                    }
+                }
+            }
+            _ => {
+                let removed = [Direction::Increasing, Direction::Decreasing]
+                    .into_iter()
+                    .filter_map(|direction| fewest_removals(levels, direction, rules))
+                    .min_by_key(|removed| removed.len());
+                if let Some(removed) = removed {
+                    return Safety::SafeWithoutLevels(removed);
+                }
             }
         }
 
-        false
+        if levels[1] < levels[0] {
+            Safety::Unsafe(decreasing)
+        } else {
+            Safety::Unsafe(increasing)
+        }
     }
 }
 
-pub fn part_1(input: &str) -> Result<(), io::Error> {
-    let (_, reports) = parse_reports(input).unwrap();
-    let valid_count = reports.iter().filter(|r| r.levels_are_valid()).count();
-    println!("{}", valid_count);
-    Ok(())
+fn explain_reports(reports: &[Report], rules: &SafetyRules) {
+    for (index, report) in reports.iter().enumerate() {
+        let levels: Vec<String> = report.levels.iter().map(|l| l.to_string()).collect();
+        match report.safety(rules) {
+            Safety::Safe => {}
+            Safety::SafeWithoutLevels(level_indices) => {
+                let removed: Vec<String> = level_indices
+                    .iter()
+                    .map(|&level_index| format!("{} ({})", level_index, report.levels[level_index]))
+                    .collect();
+                println!(
+                    "Report {}: {} is safe after removing {} {}",
+                    index,
+                    levels.join(" "),
+                    if removed.len() == 1 {
+                        "level"
+                    } else {
+                        "levels"
+                    },
+                    removed.join(", ")
+                )
+            }
+            Safety::Unsafe(violation) => println!(
+                "Report {}: {} is unsafe, {}",
+                index,
+                levels.join(" "),
+                violation
+            ),
+        }
+    }
 }
 
-pub fn part_2(input: &str) -> Result<(), io::Error> {
-    let (_, reports) = parse_reports(input).unwrap();
-    let valid_count = reports
+pub fn count_safe_reports(reports: &[Report], rules: &SafetyRules) -> usize {
+    reports
         .iter()
-        .filter(|r| r.problem_dampened_is_valid())
-        .count();
-    println!("{}", valid_count);
-    Ok(())
+        .filter(|r| !matches!(r.safety(rules), Safety::Unsafe(_)))
+        .count()
 }
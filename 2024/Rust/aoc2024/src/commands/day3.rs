@@ -1,12 +1,97 @@
-use regex::Regex;
-use std::fs::read_to_string;
-use std::io;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{self, BufWriter, Read, Write};
+use tracing::instrument;
 
-pub fn handle(input_file: std::path::PathBuf, part_number: u8) -> Result<(), io::Error> {
-    let contents = read_to_string(input_file)?;
+use crate::args::TraceFormat;
+use crate::input::open;
+
+pub fn handle(
+    input_file: std::path::PathBuf,
+    part_number: u8,
+    trace_format: Option<TraceFormat>,
+) -> Result<(), io::Error> {
+    // Day 3 is processed as a stream so a large input, including one fed from stdin with
+    // `--input-file -`, is never held in memory. Line endings and whitespace never form part of an
+    // instruction, so the stream isn't normalised.
+    let reader = open(&input_file)?;
+    let instructions = instructions(part_number)?;
+    match trace_format {
+        Some(format) => {
+            let mut output = BufWriter::new(io::stdout().lock());
+            let summary = trace(reader, instructions, format, &mut output)?;
+            output.flush()?;
+            // Keep the CSV to just the rows so it can be loaded directly, the summary goes to
+            // stderr.
+            if let TraceFormat::Csv = format {
+                eprintln!("{}", summary.text());
+            }
+            Ok(())
+        }
+        None => solve(reader, instructions),
+    }
+}
+
+pub struct State {
+    pub is_enabled: bool,
+    pub sum: i64,
+}
+
+impl State {
+    fn new() -> State {
+        State {
+            is_enabled: true,
+            sum: 0,
+        }
+    }
+}
+
+// An instruction looks like `name(a,b,...)` with exactly `arity` operands of 1-3 digits each and
+// no whitespace anywhere. Adding a new instruction only requires adding an entry to a table.
+pub struct Instruction {
+    pub name: &'static str,
+    pub arity: usize,
+    // Returns the value the instruction computed, whether or not it was added to the sum.
+    pub execute: fn(&mut State, &[u32]) -> Option<i64>,
+}
+
+pub const MUL: Instruction = Instruction {
+    name: "mul",
+    arity: 2,
+    execute: |state, operands| {
+        let product = operands[0] as i64 * operands[1] as i64;
+        if state.is_enabled {
+            state.sum += product;
+        }
+        Some(product)
+    },
+};
+
+pub const DO: Instruction = Instruction {
+    name: "do",
+    arity: 0,
+    execute: |state, _| {
+        state.is_enabled = true;
+        None
+    },
+};
+
+pub const DONT: Instruction = Instruction {
+    name: "don't",
+    arity: 0,
+    execute: |state, _| {
+        state.is_enabled = false;
+        None
+    },
+};
+
+const PART_1_INSTRUCTIONS: &[Instruction] = &[MUL];
+const PART_2_INSTRUCTIONS: &[Instruction] = &[MUL, DO, DONT];
+
+pub fn instructions(part_number: u8) -> Result<&'static [Instruction], io::Error> {
     match part_number {
-        1 => part1(&contents),
-        2 => part2(&contents),
+        1 => Ok(PART_1_INSTRUCTIONS),
+        2 => Ok(PART_2_INSTRUCTIONS),
         _ => Err(io::Error::new(
             io::ErrorKind::InvalidInput,
             "Invalid part number",
@@ -14,52 +99,354 @@ pub fn handle(input_file: std::path::PathBuf, part_number: u8) -> Result<(), io:
     }
 }
 
-fn part1(input: &str) -> Result<(), io::Error> {
-    let regex = Regex::new(r"(?m)mul\((?<f>-?[0-9]{1,3}),(?<s>-?[0-9]{1,3})\)").unwrap();
+const MAX_OPERAND_DIGITS: usize = 3;
+const CHUNK_SIZE: usize = 64 * 1024;
+
+impl Instruction {
+    fn max_length(&self) -> usize {
+        self.name.len() + 2 + self.arity * MAX_OPERAND_DIGITS + self.arity.saturating_sub(1)
+    }
+
+    // Returns the operands and the total length of the instruction if `input` starts with one.
+    fn match_at(&self, input: &[u8]) -> Option<(Vec<u32>, usize)> {
+        let name = self.name.as_bytes();
+        if !input.starts_with(name) {
+            return None;
+        }
+        let mut cursor = name.len();
+        if input.get(cursor) != Some(&b'(') {
+            return None;
+        }
+        cursor += 1;
 
-    // result will be an iterator over tuples containing the start and end indices for each match in the string
-    let result = regex.captures_iter(input);
+        let mut operands = Vec::with_capacity(self.arity);
+        for index in 0..self.arity {
+            if index > 0 {
+                if input.get(cursor) != Some(&b',') {
+                    return None;
+                }
+                cursor += 1;
+            }
+            let digits = input[cursor..]
+                .iter()
+                .take(MAX_OPERAND_DIGITS + 1)
+                .take_while(|byte| byte.is_ascii_digit())
+                .count();
+            if digits == 0 || digits > MAX_OPERAND_DIGITS {
+                return None;
+            }
+            let operand = input[cursor..cursor + digits]
+                .iter()
+                .fold(0, |value, digit| value * 10 + (digit - b'0') as u32);
+            operands.push(operand);
+            cursor += digits;
+        }
 
-    let mut sum: i32 = 0;
-    for (_, [first, second]) in result.map(|c| c.extract()) {
-        sum += first.parse::<i32>().unwrap() * second.parse::<i32>().unwrap();
+        if input.get(cursor) != Some(&b')') {
+            return None;
+        }
+        Some((operands, cursor + 1))
     }
+}
 
-    println!("{}", sum);
+pub struct Token<'a> {
+    pub instruction: &'a Instruction,
+    pub operands: Vec<u32>,
+    pub offset: u64,
+    pub line: u64,
+    pub column: u64,
+}
 
-    Ok(())
+// Reads the input in fixed size chunks and only keeps enough of the previous chunk around to
+// complete an instruction that was split across the boundary, so memory use stays bounded.
+pub struct Lexer<'a, R: Read> {
+    reader: R,
+    buffer: Vec<u8>,
+    position: usize,
+    is_exhausted: bool,
+    instructions: &'a [Instruction],
+    lookahead: usize,
+    offset: u64,
+    line: u64,
+    column: u64,
 }
 
-fn part2(input: &str) -> Result<(), io::Error> {
-    let regex = Regex::new(r"(?m)mul\((?<mul>(?<f>-?[0-9]{1,3}),(?<s>-?[0-9]{1,3}))\)|(?<do>do\(\))|(?<dont>don't\(\))").unwrap();
+impl<'a, R: Read> Lexer<'a, R> {
+    pub fn new(reader: R, instructions: &'a [Instruction]) -> Lexer<'a, R> {
+        let lookahead = instructions
+            .iter()
+            .map(Instruction::max_length)
+            .max()
+            .unwrap_or(0);
+        Lexer {
+            reader,
+            buffer: Vec::with_capacity(CHUNK_SIZE + lookahead),
+            position: 0,
+            is_exhausted: false,
+            instructions,
+            lookahead,
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn fill(&mut self) -> Result<(), io::Error> {
+        self.buffer.drain(..self.position);
+        self.position = 0;
+
+        let filled = self.buffer.len();
+        self.buffer.resize(filled + CHUNK_SIZE, 0);
+        loop {
+            match self.reader.read(&mut self.buffer[filled..]) {
+                Ok(read) => {
+                    self.buffer.truncate(filled + read);
+                    self.is_exhausted = read == 0;
+                    return Ok(());
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => {
+                    self.buffer.truncate(filled);
+                    return Err(error);
+                }
+            }
+        }
+    }
+}
 
-    // result will be an iterator over tuples containing the start and end indices for each match in the string
-    let result = regex.captures_iter(input);
+impl<'a, R: Read> Iterator for Lexer<'a, R> {
+    type Item = Result<Token<'a>, io::Error>;
 
-    let mut sum: i32 = 0;
-    let mut is_enabled: bool = true;
-    for mat in result {
-        if let Some(_mul) = mat.name("mul") {
-            if !is_enabled {
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let available = self.buffer.len() - self.position;
+            if !self.is_exhausted && available < self.lookahead {
+                if let Err(error) = self.fill() {
+                    return Some(Err(error));
+                }
                 continue;
             }
+            if available == 0 {
+                return None;
+            }
 
-            sum += mat.name("f").unwrap().as_str().parse::<i32>().unwrap()
-                * mat.name("s").unwrap().as_str().parse::<i32>().unwrap();
-        }
-        if let Some(do_match) = mat.name("do") {
-            if do_match.as_str() == "do()" {
-                is_enabled = true;
+            let remaining = &self.buffer[self.position..];
+            for instruction in self.instructions {
+                if let Some((operands, length)) = instruction.match_at(remaining) {
+                    let token = Token {
+                        instruction,
+                        operands,
+                        offset: self.offset,
+                        line: self.line,
+                        column: self.column,
+                    };
+                    // Instructions never contain a newline so only the column moves.
+                    self.position += length;
+                    self.offset += length as u64;
+                    self.column += length as u64;
+                    return Some(Ok(token));
+                }
             }
-        }
-        if let Some(dont_match) = mat.name("dont") {
-            if dont_match.as_str() == "don't()" {
-                is_enabled = false;
+            if remaining[0] == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
             }
+            self.position += 1;
+            self.offset += 1;
         }
     }
+}
+
+pub fn run<R: Read>(reader: R, instructions: &[Instruction]) -> Result<State, io::Error> {
+    let mut state = State::new();
+    for token in Lexer::new(reader, instructions) {
+        let token = token?;
+        (token.instruction.execute)(&mut state, &token.operands);
+    }
+    Ok(state)
+}
+
+// Lexes every instruction either part understands, each part then only executes its own.
+#[instrument(skip_all)]
+pub fn parse(contents: &str) -> Result<Vec<Token<'static>>, io::Error> {
+    Lexer::new(contents.as_bytes(), PART_2_INSTRUCTIONS).collect()
+}
 
-    println!("{}", sum);
+fn execute(tokens: &[Token], instructions: &[Instruction]) -> State {
+    let mut state = State::new();
+    for token in tokens {
+        if instructions
+            .iter()
+            .any(|instruction| instruction.name == token.instruction.name)
+        {
+            (token.instruction.execute)(&mut state, &token.operands);
+        }
+    }
+    state
+}
+
+#[instrument(skip_all)]
+pub fn part_1(tokens: &[Token]) -> Result<i64, io::Error> {
+    Ok(execute(tokens, PART_1_INSTRUCTIONS).sum)
+}
 
+#[instrument(skip_all)]
+pub fn part_2(tokens: &[Token]) -> Result<i64, io::Error> {
+    Ok(execute(tokens, PART_2_INSTRUCTIONS).sum)
+}
+
+fn solve<R: Read>(reader: R, instructions: &[Instruction]) -> Result<(), io::Error> {
+    let state = run(reader, instructions)?;
+    println!("{}", state.sum);
     Ok(())
 }
+
+#[derive(Serialize)]
+struct TraceEntry {
+    offset: u64,
+    line: u64,
+    column: u64,
+    instruction: &'static str,
+    operands: Vec<u32>,
+    value: Option<i64>,
+    enabled: bool,
+}
+
+impl TraceEntry {
+    fn text(&self) -> String {
+        let operands: Vec<String> = self.operands.iter().map(|o| o.to_string()).collect();
+        let call = format!("{}({})", self.instruction, operands.join(","));
+        let position = format!("{}:{}", self.line, self.column);
+        match self.value {
+            Some(value) => format!(
+                "{:>10} {:<10} {:<14} = {:<8} {}",
+                self.offset,
+                position,
+                call,
+                value,
+                if self.enabled { "enabled" } else { "disabled" }
+            ),
+            None => format!("{:>10} {:<10} {}", self.offset, position, call),
+        }
+    }
+
+    fn csv(&self) -> String {
+        let operands: Vec<String> = self.operands.iter().map(|o| o.to_string()).collect();
+        format!(
+            "{},{},{},{},{},{},{}",
+            self.offset,
+            self.line,
+            self.column,
+            self.instruction,
+            operands.join(" "),
+            self.value.map(|v| v.to_string()).unwrap_or_default(),
+            self.enabled
+        )
+    }
+}
+
+#[derive(Serialize, Default, Debug, PartialEq)]
+pub struct TraceSummary {
+    pub instruction_counts: BTreeMap<&'static str, u64>,
+    pub enabled_count: u64,
+    pub enabled_sum: i64,
+    pub disabled_count: u64,
+    pub disabled_sum: i64,
+}
+
+impl TraceSummary {
+    fn record(&mut self, entry: &TraceEntry) {
+        *self
+            .instruction_counts
+            .entry(entry.instruction)
+            .or_default() += 1;
+        if let Some(value) = entry.value {
+            if entry.enabled {
+                self.enabled_count += 1;
+                self.enabled_sum += value;
+            } else {
+                self.disabled_count += 1;
+                self.disabled_sum += value;
+            }
+        }
+    }
+
+    pub fn text(&self) -> String {
+        let counts: Vec<String> = self
+            .instruction_counts
+            .iter()
+            .map(|(name, count)| format!("{}: {}", name, count))
+            .collect();
+        [
+            format!("instructions: {}", counts.join(", ")),
+            format!("enabled: {} (sum {})", self.enabled_count, self.enabled_sum),
+            format!(
+                "disabled: {} (sum {})",
+                self.disabled_count, self.disabled_sum
+            ),
+            format!("total: {}", self.enabled_sum + self.disabled_sum),
+        ]
+        .join("\n")
+    }
+}
+
+// The last line of a JSON trace, after one line for each entry.
+#[derive(Serialize)]
+struct TraceEnd<'a> {
+    summary: &'a TraceSummary,
+}
+
+// Writes an entry for every instruction as it is lexed, so the trace takes as little memory as
+// solving does. JSON is written as JSON Lines for the same reason, with the summary on the last
+// line. The CSV is only the rows, the summary is returned for the caller to report.
+pub fn trace<R: Read, W: Write>(
+    reader: R,
+    instructions: &[Instruction],
+    format: TraceFormat,
+    mut output: W,
+) -> Result<TraceSummary, io::Error> {
+    let mut state = State::new();
+    let mut summary = TraceSummary::default();
+
+    if let TraceFormat::Csv = format {
+        writeln!(
+            output,
+            "offset,line,column,instruction,operands,value,enabled"
+        )?;
+    }
+    for token in Lexer::new(reader, instructions) {
+        let token = token?;
+        let enabled = state.is_enabled;
+        let value = (token.instruction.execute)(&mut state, &token.operands);
+        let entry = TraceEntry {
+            offset: token.offset,
+            line: token.line,
+            column: token.column,
+            instruction: token.instruction.name,
+            operands: token.operands,
+            value,
+            enabled,
+        };
+        summary.record(&entry);
+        match format {
+            TraceFormat::Text => writeln!(output, "{}", entry.text())?,
+            TraceFormat::Csv => writeln!(output, "{}", entry.csv())?,
+            TraceFormat::Json => {
+                serde_json::to_writer(&mut output, &entry)?;
+                writeln!(output)?;
+            }
+        }
+    }
+
+    match format {
+        TraceFormat::Text => writeln!(output, "\n{}", summary.text())?,
+        TraceFormat::Csv => {}
+        TraceFormat::Json => {
+            serde_json::to_writer(&mut output, &TraceEnd { summary: &summary })?;
+            writeln!(output)?;
+        }
+    }
+    Ok(summary)
+}
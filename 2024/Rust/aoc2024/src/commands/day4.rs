@@ -1,21 +1,71 @@
 use enum_iterator::{all, Sequence};
-use std::fs;
-use std::io;
+use std::collections::HashSet;
+use std::env;
+use std::io::{self, IsTerminal};
+use tracing::instrument;
 
-pub fn handle(input_file: std::path::PathBuf, part_number: u8) -> Result<(), io::Error> {
-    let contents = fs::read_to_string(input_file)?;
+use crate::input::read_input;
+use crate::parsing::{grid, parse_all};
 
-    match part_number {
-        1 => part1(&contents),
-        2 => part2(&contents),
-        _ => Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            "Invalid part number",
-        )),
+pub fn handle(
+    input_file: std::path::PathBuf,
+    part_number: u8,
+    words: Vec<String>,
+    templates: Vec<String>,
+    show: bool,
+    list: bool,
+) -> Result<(), io::Error> {
+    let contents = read_input(&input_file)?;
+
+    let (words, templates) = if !words.is_empty() || !templates.is_empty() {
+        (words, templates)
+    } else {
+        match part_number {
+            1 => (vec!["XMAS".to_string()], vec![]),
+            2 => (vec![], vec!["x-mas".to_string()]),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Invalid part number",
+                ))
+            }
+        }
+    };
+
+    let grid = parse(&contents)?;
+    if show || list {
+        let matches = find_matches(&grid, &words, &templates)?;
+        if list {
+            print!("{}", listing(&matches));
+        }
+        if show {
+            let use_colour = io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+            print!("{}", highlighted(&grid, &matches, use_colour));
+        }
     }
+
+    search(&grid, &words, &templates)
 }
 
-#[derive(Debug, Sequence, Copy, Clone)]
+#[instrument(skip_all)]
+pub fn parse(contents: &str) -> Result<Grid, io::Error> {
+    let rows = parse_all(contents, "a grid of letters", grid)?;
+    Ok(Grid {
+        lines: rows.iter().map(|row| row.as_bytes().to_vec()).collect(),
+    })
+}
+
+#[instrument(skip_all)]
+pub fn part_1(grid: &Grid) -> Result<usize, io::Error> {
+    Ok(count_word(grid, "XMAS"))
+}
+
+#[instrument(skip_all)]
+pub fn part_2(grid: &Grid) -> Result<usize, io::Error> {
+    Ok(count_template(grid, &Template::parse("x-mas")?))
+}
+
+#[derive(Debug, Sequence, Copy, Clone, PartialEq)]
 enum Direction {
     Up,
     Down,
@@ -28,189 +78,376 @@ enum Direction {
 }
 
 impl Direction {
-    fn indices_from_line_and_character(
-        &self,
-        line: usize,
-        character: usize,
-        length: usize,
-        max_line_count: usize,
-        max_character_count: usize,
-    ) -> Vec<(usize, usize)> {
-        let range = 0..length;
+    // The (line, character) step taken to read the next letter.
+    fn offset(&self) -> (isize, isize) {
         match self {
-            Direction::Up => {
-                if line < length - 1 {
-                    return vec![];
-                }
-                range.map(|i| (line - i, character)).collect()
-            }
-            Direction::Down => {
-                if line + length > max_line_count {
-                    return vec![];
-                }
-                range.map(|i| (line + i, character)).collect()
-            }
-            Direction::Left => {
-                if character < length - 1 {
-                    return vec![];
-                }
-                range.map(|i| (line, character - i)).collect()
-            }
-            Direction::Right => {
-                if character + length > max_character_count {
-                    return vec![];
-                }
-                range.map(|i| (line, character + i)).collect()
-            }
-            Direction::LeftUp => {
-                if line < length - 1 || character < length - 1 {
-                    return vec![];
-                }
-                range.map(|i| (line - i, character - i)).collect()
+            Direction::Up => (-1, 0),
+            Direction::Down => (1, 0),
+            Direction::Left => (0, -1),
+            Direction::Right => (0, 1),
+            Direction::LeftUp => (-1, -1),
+            Direction::LeftDown => (1, -1),
+            Direction::RightUp => (-1, 1),
+            Direction::RightDown => (1, 1),
+        }
+    }
+
+    // One of each pair of opposite directions: the ones that step right, and down.
+    fn is_forward(&self) -> bool {
+        let (line_step, character_step) = self.offset();
+        character_step > 0 || (character_step == 0 && line_step > 0)
+    }
+}
+
+// The grid is searched as raw ASCII bytes.
+pub struct Grid {
+    lines: Vec<Vec<u8>>,
+}
+
+impl Grid {
+    // Lines may have different lengths, anything outside of a line is treated as off the grid.
+    fn byte_at(&self, line: isize, character: isize) -> Option<u8> {
+        if line < 0 || character < 0 {
+            return None;
+        }
+        self.lines
+            .get(line as usize)
+            .and_then(|bytes| bytes.get(character as usize))
+            .copied()
+    }
+
+    // Every cell holding `letter`, so searches only start where a match can begin.
+    fn positions_of(&self, letter: u8) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.lines
+            .iter()
+            .enumerate()
+            .flat_map(move |(line, bytes)| {
+                bytes
+                    .iter()
+                    .enumerate()
+                    .filter(move |(_, &byte)| byte == letter)
+                    .map(move |(character, _)| (line, character))
+            })
+    }
+}
+
+fn word_matches_at(
+    grid: &Grid,
+    word: &[u8],
+    line: usize,
+    character: usize,
+    direction: Direction,
+) -> bool {
+    let (line_step, character_step) = direction.offset();
+    let mut next_line = line as isize;
+    let mut next_character = character as isize;
+    for &letter in &word[1..] {
+        next_line += line_step;
+        next_character += character_step;
+        if grid.byte_at(next_line, next_character) != Some(letter) {
+            return false;
+        }
+    }
+    true
+}
+
+// Calls `on_match` with the start and direction of every occurrence of `word`.
+fn scan_word(grid: &Grid, word: &[u8], mut on_match: impl FnMut(usize, usize, Direction)) {
+    let Some(&first) = word.first() else {
+        return;
+    };
+    // An occurrence is counted once per set of cells. A word that reads the same backwards is
+    // also found from its last letter in the opposite direction, so only forward directions are
+    // searched for it, and a single letter reads the same in every direction so it takes one.
+    let palindrome = word.iter().eq(word.iter().rev());
+    let direction_count = if word.len() == 1 { 1 } else { 8 };
+    for (line, character) in grid.positions_of(first) {
+        for direction in all::<Direction>()
+            .filter(|direction| !palindrome || direction.is_forward())
+            .take(direction_count)
+        {
+            if word_matches_at(grid, word, line, character, direction) {
+                on_match(line, character, direction);
             }
-            Direction::LeftDown => {
-                if line + length > max_line_count || character < length - 1 {
-                    return vec![];
+        }
+    }
+}
+
+pub fn count_word(grid: &Grid, word: &str) -> usize {
+    let mut count = 0;
+    scan_word(grid, word.as_bytes(), |_, _, _| count += 1);
+    count
+}
+
+const WILDCARD: u8 = b'.';
+
+// A 2D pattern of letters where `.` matches anything. Rows are separated by `/` or newlines, so
+// the X-MAS cross is `M.S/.A./M.S`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    name: String,
+    cells: Vec<(isize, isize, u8)>,
+}
+
+impl Template {
+    fn builtin(name: &str) -> Option<&'static str> {
+        match name {
+            "x-mas" => Some("M.S/.A./M.S"),
+            "plus" => Some(".M./MAS/.S."),
+            _ => None,
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Template, io::Error> {
+        let specification = Template::builtin(name).unwrap_or(name);
+        let mut cells = Vec::new();
+        for (line, row) in specification.split(['/', '\n']).enumerate() {
+            for (character, letter) in row.bytes().enumerate() {
+                if letter != WILDCARD {
+                    cells.push((line as isize, character as isize, letter));
                 }
-                range.map(|i| (line + i, character - i)).collect()
             }
-            Direction::RightUp => {
-                if line < length - 1 || character + length > max_character_count {
-                    return vec![];
+        }
+        if cells.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Template {:?} has no letters", specification),
+            ));
+        }
+        Ok(Template {
+            name: name.to_string(),
+            cells,
+        }
+        .normalized())
+    }
+
+    // Moves the template so its top left letter is at (0, 0) and orders the cells, so that two
+    // templates covering the same shape compare as equal.
+    fn normalized(mut self) -> Template {
+        let min_line = self.cells.iter().map(|cell| cell.0).min().unwrap_or(0);
+        let min_character = self.cells.iter().map(|cell| cell.1).min().unwrap_or(0);
+        for cell in self.cells.iter_mut() {
+            cell.0 -= min_line;
+            cell.1 -= min_character;
+        }
+        self.cells.sort();
+        self
+    }
+
+    fn rotated(&self) -> Template {
+        Template {
+            name: self.name.clone(),
+            cells: self
+                .cells
+                .iter()
+                .map(|&(line, character, letter)| (character, -line, letter))
+                .collect(),
+        }
+        .normalized()
+    }
+
+    fn reflected(&self) -> Template {
+        Template {
+            name: self.name.clone(),
+            cells: self
+                .cells
+                .iter()
+                .map(|&(line, character, letter)| (line, -character, letter))
+                .collect(),
+        }
+        .normalized()
+    }
+
+    // Every distinct rotation and reflection of the template.
+    pub fn variants(&self) -> Vec<Template> {
+        self.orientations()
+            .into_iter()
+            .map(|(_, variant)| variant)
+            .collect()
+    }
+
+    // The distinct variants, each named after the first way found to make it: `rot90` is a
+    // quarter turn clockwise and `+flip` then mirrors it left to right.
+    fn orientations(&self) -> Vec<(String, Template)> {
+        let mut orientations: Vec<(String, Template)> = Vec::new();
+        let mut rotation = self.clone();
+        for quarter_turns in 0..4 {
+            let name = format!("rot{}", quarter_turns * 90);
+            let flipped = format!("{}+flip", name);
+            for (name, variant) in [(name, rotation.clone()), (flipped, rotation.reflected())] {
+                if !orientations
+                    .iter()
+                    .any(|(_, existing)| *existing == variant)
+                {
+                    orientations.push((name, variant));
                 }
-                range.map(|i| (line - i, character + i)).collect()
             }
-            Direction::RightDown => {
-                if line + length > max_line_count || character + length > max_character_count {
-                    return vec![];
-                }
-                range.map(|i| (line + i, character + i)).collect()
+            rotation = rotation.rotated();
+        }
+        orientations
+    }
+
+    // Calls `on_match` with the top left corner of every placement of this exact variant. The
+    // first cell is always on the top line, so only cells holding its letter are tried.
+    fn scan(&self, grid: &Grid, mut on_match: impl FnMut(usize, usize)) {
+        let (_, first_character, first_letter) = self.cells[0];
+        for (line, character) in grid.positions_of(first_letter) {
+            let Some(corner) = character.checked_sub(first_character as usize) else {
+                continue;
+            };
+            let matches = self.cells[1..]
+                .iter()
+                .all(|&(line_offset, character_offset, letter)| {
+                    grid.byte_at(
+                        line as isize + line_offset,
+                        corner as isize + character_offset,
+                    ) == Some(letter)
+                });
+            if matches {
+                on_match(line, corner);
             }
         }
     }
 }
 
-fn collect_characters_beginning_at_line_and_character(
-    lines: &[Vec<char>],
-    line: usize,
-    character: usize,
-    length: usize,
-    direction: &Direction,
-) -> Vec<char> {
-    let mut characters = vec![];
-    let indices = direction.indices_from_line_and_character(
-        line,
-        character,
-        length,
-        lines.len(),
-        lines[line].len(),
-    );
-    for (next_line_index, next_character_index) in indices {
-        characters.push(lines[next_line_index][next_character_index]);
-    }
-    characters
-}
-
-fn count_of_string_beginning_at_line_and_character(
-    input: &str,
-    lines: &[Vec<char>],
-    line: usize,
-    character: usize,
-    length: usize,
-) -> usize {
+pub fn count_template(grid: &Grid, template: &Template) -> usize {
     let mut count = 0;
-    for direction in all::<Direction>() {
-        let characters = collect_characters_beginning_at_line_and_character(
-            lines, line, character, length, &direction,
-        );
-        if input.eq(&characters.iter().collect::<String>()) {
-            count += 1;
-        }
+    for variant in template.variants() {
+        variant.scan(grid, |_, _| count += 1);
     }
     count
 }
 
-fn part1(contents: &str) -> Result<(), io::Error> {
-    // split the contents into lines
-    // split each line into characters
-    let lines: Vec<Vec<char>> = contents
-        .lines()
-        .map(|line| line.chars().collect())
-        .collect();
+// Where a word or template was found, with the direction of a word or the orientation of a
+// template.
+#[derive(Debug, PartialEq)]
+pub struct Match {
+    pub pattern: String,
+    pub line: usize,
+    pub character: usize,
+    pub orientation: String,
+    pub cells: Vec<(usize, usize)>,
+}
 
-    let mut count = 0;
-    for line in 0..lines.len() {
-        for character in 0..lines[line].len() {
-            count +=
-                count_of_string_beginning_at_line_and_character("XMAS", &lines, line, character, 4);
-        }
+pub fn find_matches(
+    grid: &Grid,
+    words: &[String],
+    templates: &[String],
+) -> Result<Vec<Match>, io::Error> {
+    let mut matches = Vec::new();
+    for word in words {
+        matches.extend(find_word(grid, word));
     }
-
-    println!("{}", count);
-    Ok(())
+    for specification in templates {
+        matches.extend(find_template(grid, &Template::parse(specification)?));
+    }
+    Ok(matches)
 }
 
-fn x_mases_beginning_at_line_and_character(
-    lines: &[Vec<char>],
-    line: usize,
-    character: usize,
-) -> usize {
-    if line < 1 || line == lines.len() - 1 || character < 1 || character == lines[line].len() - 1 {
-        return 0;
-    }
-
-    let left_up = collect_characters_beginning_at_line_and_character(
-        lines,
-        line - 1,
-        character - 1,
-        3,
-        &Direction::RightDown,
-    );
-    let left_down = collect_characters_beginning_at_line_and_character(
-        lines,
-        line + 1,
-        character - 1,
-        3,
-        &Direction::RightUp,
-    );
-    let right_up = collect_characters_beginning_at_line_and_character(
-        lines,
-        line - 1,
-        character + 1,
-        3,
-        &Direction::LeftDown,
-    );
-    let right_down = collect_characters_beginning_at_line_and_character(
-        lines,
-        line + 1,
-        character + 1,
-        3,
-        &Direction::LeftUp,
-    );
-    let mut count = 0;
-    let forward_mas = vec!['M', 'A', 'S'];
-    let reverse_mas = forward_mas.iter().rev().cloned().collect::<Vec<char>>();
-    if [left_up, left_down, right_up, right_down]
+// What `--list` prints, a line for every match.
+pub fn listing(matches: &[Match]) -> String {
+    matches
         .iter()
-        .all(|direction| direction.eq(&forward_mas) || direction.eq(&reverse_mas))
-    {
-        count += 1;
+        .map(|found| {
+            format!(
+                "{} at ({}, {}) {}\n",
+                found.pattern, found.line, found.character, found.orientation
+            )
+        })
+        .collect()
+}
+
+fn find_word(grid: &Grid, word: &str) -> Vec<Match> {
+    let mut matches = Vec::new();
+    scan_word(grid, word.as_bytes(), |line, character, direction| {
+        let (line_step, character_step) = direction.offset();
+        let cells = (0..word.len() as isize)
+            .map(|i| {
+                (
+                    (line as isize + line_step * i) as usize,
+                    (character as isize + character_step * i) as usize,
+                )
+            })
+            .collect();
+        matches.push(Match {
+            pattern: word.to_string(),
+            line,
+            character,
+            orientation: format!("{:?}", direction),
+            cells,
+        });
+    });
+    matches
+}
+
+fn find_template(grid: &Grid, template: &Template) -> Vec<Match> {
+    let mut matches = Vec::new();
+    for (orientation, variant) in template.orientations() {
+        variant.scan(grid, |line, character| {
+            let cells = variant
+                .cells
+                .iter()
+                .map(|&(line_offset, character_offset, _)| {
+                    (
+                        (line as isize + line_offset) as usize,
+                        (character as isize + character_offset) as usize,
+                    )
+                })
+                .collect();
+            matches.push(Match {
+                pattern: template.name.clone(),
+                line,
+                character,
+                orientation: orientation.clone(),
+                cells,
+            });
+        });
     }
-    count
+    matches
 }
 
-fn part2(contents: &str) -> Result<(), io::Error> {
-    let lines: Vec<Vec<char>> = contents
-        .lines()
-        .map(|line| line.chars().collect())
+// Letters that are part of a match are kept and everything else is replaced by `.` like in the
+// puzzle statement. With colour matches are shown in bold green and the rest dimmed.
+pub fn highlighted(grid: &Grid, matches: &[Match], use_colour: bool) -> String {
+    let highlighted: HashSet<(usize, usize)> = matches
+        .iter()
+        .flat_map(|found| found.cells.iter().cloned())
         .collect();
+    let mut output = String::new();
+    for (line, bytes) in grid.lines.iter().enumerate() {
+        for (character, letter) in bytes.iter().map(|&byte| byte as char).enumerate() {
+            match (highlighted.contains(&(line, character)), use_colour) {
+                (true, true) => output.push_str(&format!("\x1b[1;32m{}\x1b[0m", letter)),
+                (true, false) => output.push(letter),
+                (false, true) => output.push_str("\x1b[2m.\x1b[0m"),
+                (false, false) => output.push('.'),
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
 
-    let mut count = 0;
-    for line in 0..lines.len() {
-        for character in 0..lines[line].len() {
-            count += x_mases_beginning_at_line_and_character(&lines, line, character);
+fn search(grid: &Grid, words: &[String], templates: &[String]) -> Result<(), io::Error> {
+    let mut counts = Vec::new();
+    for word in words {
+        counts.push((word.clone(), count_word(grid, word)));
+    }
+    for specification in templates {
+        let template = Template::parse(specification)?;
+        counts.push((specification.clone(), count_template(grid, &template)));
+    }
+
+    if counts.len() == 1 {
+        println!("{}", counts[0].1);
+    } else {
+        for (pattern, count) in &counts {
+            println!("{}: {}", pattern, count);
         }
+        println!(
+            "total: {}",
+            counts.iter().map(|(_, count)| count).sum::<usize>()
+        );
     }
-    println!("{}", count);
     Ok(())
 }
@@ -1,18 +1,15 @@
-// use std::collections:HashMap;
-use nom::{
-    bytes::complete::tag,
-    character::complete::{digit1, newline},
-    combinator::map_res,
-    multi::separated_list1,
-    sequence::separated_pair,
-    IResult,
-};
-use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
-use std::fs::read_to_string;
+use nom::{character::complete::char, sequence::separated_pair};
+use serde::Serialize;
+use std::collections::{btree_set, BTreeMap, BTreeSet, HashMap, HashSet};
 use std::io;
+use std::{fmt, iter::zip};
+use tracing::{info, instrument};
 
-#[derive(Debug, PartialEq)]
+use crate::args::{Day5Mode, GraphFormat};
+use crate::input::read_input;
+use crate::parsing::{comma_separated, lines, parse_all, sections, unsigned, ParseResult};
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
 pub struct Rule {
     pub first: u32,
     pub second: u32,
@@ -23,116 +20,228 @@ pub struct Update {
     pub pages: Vec<u32>,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+    pub rule: Rule,
+    pub first_position: usize,
+    pub second_position: usize,
+}
+
 impl Update {
-    fn is_valid(&self, rules: &HashMap<u32, HashSet<u32>>) -> bool {
-        let mut seen_pages: HashSet<u32> = HashSet::new();
-        for page in self.pages.clone() {
-            if let Some(required_pages_after) = rules.get(&page) {
+    pub fn violations(&self, rules: &HashMap<u32, HashSet<u32>>) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for (first_position, page) in self.pages.iter().enumerate() {
+            if let Some(required_pages_after) = rules.get(page) {
+                for (second_position, seen_page) in self.pages[..first_position].iter().enumerate()
+                {
+                    if required_pages_after.contains(seen_page) {
+                        violations.push(Violation {
+                            rule: Rule {
+                                first: *page,
+                                second: *seen_page,
+                            },
+                            first_position,
+                            second_position,
+                        });
+                    }
+                }
+            }
+        }
+        violations
+    }
+
+    // Stops at the first page that has to come before one already seen, without collecting the
+    // violations, since this is checked for every update.
+    pub fn is_valid(&self, rules: &HashMap<u32, HashSet<u32>>) -> bool {
+        let mut seen_pages: HashSet<u32> = HashSet::with_capacity(self.pages.len());
+        for page in &self.pages {
+            if let Some(required_pages_after) = rules.get(page) {
                 if !required_pages_after.is_disjoint(&seen_pages) {
                     return false;
                 }
             }
-            seen_pages.insert(page);
+            seen_pages.insert(*page);
         }
         true
     }
-}
 
-fn str_to_u32(input: &str) -> IResult<&str, u32> {
-    map_res(digit1, str::parse)(input)
+    // Places the pages one at a time, always taking the smallest page that no other remaining
+    // page has to come before. Unlike sorting with the rules as a comparator this copes with rules
+    // that are not a total order, and a cycle is broken by taking the smallest remaining page.
+    fn corrected(&self, rules: &HashMap<u32, HashSet<u32>>) -> Update {
+        let mut remaining = self.pages.clone();
+        let mut pages = Vec::with_capacity(remaining.len());
+        while !remaining.is_empty() {
+            let is_free = |page: u32| {
+                !remaining.iter().any(|&other| {
+                    other != page && rules.get(&other).is_some_and(|after| after.contains(&page))
+                })
+            };
+            let smallest = |free_only: bool| {
+                (0..remaining.len())
+                    .filter(|&index| !free_only || is_free(remaining[index]))
+                    .min_by_key(|&index| remaining[index])
+            };
+            let index = smallest(true).or_else(|| smallest(false)).unwrap();
+            pages.push(remaining.remove(index));
+        }
+        Update { pages }
+    }
+
+    // What `--explain` prints for the update: the rules it breaks and the corrected order, with
+    // the positions that changed marked. Nothing when it breaks no rules.
+    pub fn explanation(&self, index: usize, rules: &HashMap<u32, HashSet<u32>>) -> Option<String> {
+        let violations = self.violations(rules);
+        if violations.is_empty() {
+            return None;
+        }
+
+        let mut lines = vec![format!("Update {}: {}", index, self)];
+        for violation in &violations {
+            lines.push(format!(
+                "  breaks {}|{}: {} at position {}, {} at position {}",
+                violation.rule.first,
+                violation.rule.second,
+                violation.rule.first,
+                violation.first_position,
+                violation.rule.second,
+                violation.second_position
+            ));
+        }
+
+        let corrected = self.corrected(rules);
+        let width = self
+            .pages
+            .iter()
+            .map(|page| page.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let row = |update: &Update| {
+            update
+                .pages
+                .iter()
+                .map(|page| format!("{:>width$}", page, width = width))
+                .collect::<Vec<String>>()
+                .join(" ")
+        };
+        let markers = zip(&self.pages, &corrected.pages)
+            .map(|(original, corrected)| {
+                let marker = if original == corrected { " " } else { "^" };
+                marker.repeat(width)
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+        lines.push(format!("  - {}", row(self)));
+        lines.push(format!("  + {}", row(&corrected)));
+        // A cycle can leave every page where it was, with nothing to point at.
+        if !markers.trim_end().is_empty() {
+            lines.push(format!("    {}", markers.trim_end()));
+        }
+        if !corrected.is_valid(rules) {
+            lines.push("  (the rules cannot fully order this update)".to_string());
+        }
+        Some(lines.join("\n") + "\n")
+    }
 }
 
-fn parse_rule(input: &str) -> IResult<&str, Rule> {
-    let (input, (first, second)) = separated_pair(str_to_u32, tag("|"), str_to_u32)(input)?;
-    Ok((input, Rule { first, second }))
+impl fmt::Display for Update {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pages: Vec<String> = self.pages.iter().map(|page| page.to_string()).collect();
+        write!(f, "{}", pages.join(","))
+    }
 }
 
-fn parse_rules(input: &str) -> IResult<&str, Vec<Rule>> {
-    let (input, rules) = separated_list1(newline, parse_rule)(input)?;
-    Ok((input, rules))
+fn parse_rule(input: &str) -> ParseResult<'_, Rule> {
+    let (input, (first, second)) = separated_pair(unsigned, char('|'), unsigned)(input)?;
+    Ok((input, Rule { first, second }))
 }
 
-fn parse_update(input: &str) -> IResult<&str, Update> {
-    let (input, pages) = separated_list1(tag(","), str_to_u32)(input)?;
+fn parse_update(input: &str) -> ParseResult<'_, Update> {
+    let (input, pages) = comma_separated(unsigned)(input)?;
     Ok((input, Update { pages }))
 }
 
-fn parse_updates(input: &str) -> IResult<&str, Vec<Update>> {
-    let (input, updates) = separated_list1(newline, parse_update)(input)?;
-    Ok((input, updates))
+pub fn rule_map(rules: &[Rule]) -> HashMap<u32, HashSet<u32>> {
+    let mut rule_map: HashMap<u32, HashSet<u32>> = HashMap::new();
+    for rule in rules {
+        rule_map.entry(rule.first).or_default().insert(rule.second);
+    }
+    rule_map
 }
 
-fn parse_input(input: &str) -> IResult<&str, (Vec<Rule>, Vec<Update>)> {
-    let (input, (rules, updates)) = separated_pair(parse_rules, tag("\n\n"), parse_updates)(input)?;
-    Ok((input, (rules, updates)))
+#[instrument(skip_all)]
+pub fn parse(contents: &str) -> Result<(Vec<Rule>, Vec<Update>), io::Error> {
+    parse_all(
+        contents,
+        "a list of rules followed by a list of updates",
+        sections(lines(parse_rule), lines(parse_update)),
+    )
 }
 
-pub fn handle(input_file: std::path::PathBuf, part_number: u8) -> Result<(), io::Error> {
-    println!("Day 5");
-    match part_number {
-        1 => part1(input_file),
-        2 => part2(input_file),
-        _ => Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            "Invalid part number",
-        )),
+pub fn handle(
+    input_file: std::path::PathBuf,
+    part_number: u8,
+    explain: bool,
+    mode: Option<Day5Mode>,
+) -> Result<(), io::Error> {
+    let contents = read_input(&input_file)?;
+    let (rules, updates) = parse(&contents)?;
+    if let Some(Day5Mode::Graph { format, update }) = mode {
+        return graph(&rules, &updates, format, update);
     }
-}
 
-fn part1(input_file: std::path::PathBuf) -> Result<(), io::Error> {
-    let contents = read_to_string(input_file)?;
-    let (_, (rules, updates)) = parse_input(&contents).unwrap();
-    // println!("{:?}", rules);
-    // println!("{:?}", updates);
-
-    let mut rule_map: HashMap<u32, HashSet<u32>> = HashMap::new();
-    for rule in rules {
-        rule_map.entry(rule.first).or_default().insert(rule.second);
+    info!(
+        rules = rules.len(),
+        updates = updates.len(),
+        "Parsed the rules and updates"
+    );
+    if explain {
+        let rule_map = rule_map(&rules);
+        for (index, update) in updates.iter().enumerate() {
+            if let Some(explanation) = update.explanation(index, &rule_map) {
+                print!("{}", explanation);
+            }
+        }
     }
+    let middle_value_sum = match part_number {
+        1 => part_1(&rules, &updates)?,
+        2 => part_2(&rules, &updates)?,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid part number",
+            ))
+        }
+    };
+    // Only the answer goes to stdout, the same as with `--time` and in the answers file.
+    info!("Middle value sum: {}", middle_value_sum);
+    println!("{}", middle_value_sum);
+    Ok(())
+}
+
+#[instrument(skip_all)]
+pub fn part_1(rules: &[Rule], updates: &[Update]) -> Result<u32, io::Error> {
+    let rule_map = rule_map(rules);
 
     let mut middle_value_sum = 0;
     'update_loop: for update in updates {
         if !update.is_valid(&rule_map) {
-            println!("Invalid update: {:?}", update);
             continue 'update_loop;
         }
 
         middle_value_sum += update.pages[update.pages.len() / 2];
     }
-    println!("Middle value sum: {}", middle_value_sum);
-    Ok(())
+    Ok(middle_value_sum)
 }
 
-fn part2(input_file: std::path::PathBuf) -> Result<(), io::Error> {
-    let contents = read_to_string(input_file)?;
-    let (_, (rules, updates)) = parse_input(&contents).unwrap();
-
-    let mut rule_map: HashMap<u32, HashSet<u32>> = HashMap::new();
-    for rule in rules {
-        rule_map.entry(rule.first).or_default().insert(rule.second);
-    }
+#[instrument(skip_all)]
+pub fn part_2(rules: &[Rule], updates: &[Update]) -> Result<u32, io::Error> {
+    let rule_map = rule_map(rules);
 
     let invalid_updates = updates.iter().filter(|update| !update.is_valid(&rule_map));
     let mut corrected_updates: Vec<Update> = Vec::new();
     for invalid_update in invalid_updates {
-        let mut other_pages = invalid_update.pages.clone();
-        other_pages.sort_by(|a, b| {
-            if let Some(first_requirements) = rule_map.get(a) {
-                // If the second number is required to come after the first, then we need to indicate that the first is "less" that the second.
-                if first_requirements.contains(b) {
-                    return Ordering::Less;
-                }
-            }
-            if let Some(second_requirements) = rule_map.get(b) {
-                // If the first number is required to come after the second, then we need to indicate that the first is "greater" than the second.
-                if second_requirements.contains(a) {
-                    return Ordering::Greater;
-                }
-            }
-            // If neither number requires the other to come first, then we can sort them naturally.
-            a.cmp(b)
-        });
-        let potential_update = Update { pages: other_pages };
+        let potential_update = invalid_update.corrected(&rule_map);
         if potential_update.is_valid(&rule_map) {
             corrected_updates.push(potential_update);
         }
@@ -142,6 +251,293 @@ fn part2(input_file: std::path::PathBuf) -> Result<(), io::Error> {
         .iter()
         .map(|update| update.pages[update.pages.len() / 2])
         .sum();
-    println!("Middle value sum: {}", middle_value_sum);
+    Ok(middle_value_sum)
+}
+
+struct RuleGraph {
+    edges: BTreeMap<u32, BTreeSet<u32>>,
+}
+
+impl RuleGraph {
+    fn new(rules: &[Rule]) -> RuleGraph {
+        let mut edges: BTreeMap<u32, BTreeSet<u32>> = BTreeMap::new();
+        for rule in rules {
+            edges.entry(rule.first).or_default().insert(rule.second);
+            edges.entry(rule.second).or_default();
+        }
+        RuleGraph { edges }
+    }
+
+    fn rules(&self) -> impl Iterator<Item = Rule> + '_ {
+        self.edges
+            .iter()
+            .flat_map(|(&first, seconds)| seconds.iter().map(move |&second| Rule { first, second }))
+    }
+
+    // Tarjan's algorithm, components come out in reverse topological order. The search keeps its
+    // own stack of pages being visited rather than recursing, so a long chain of rules can't
+    // overflow the thread's stack.
+    fn strongly_connected_components(&self) -> Vec<Vec<u32>> {
+        struct Search<'a> {
+            next_index: usize,
+            indices: HashMap<u32, usize>,
+            low_links: HashMap<u32, usize>,
+            stack: Vec<u32>,
+            on_stack: HashSet<u32>,
+            components: Vec<Vec<u32>>,
+            // The pages being visited, each with the rules from it that are left to follow.
+            visiting: Vec<(u32, btree_set::Iter<'a, u32>)>,
+        }
+
+        impl<'a> Search<'a> {
+            fn enter(&mut self, graph: &'a RuleGraph, page: u32) {
+                self.indices.insert(page, self.next_index);
+                self.low_links.insert(page, self.next_index);
+                self.next_index += 1;
+                self.stack.push(page);
+                self.on_stack.insert(page);
+                self.visiting.push((page, graph.edges[&page].iter()));
+            }
+
+            fn lower_low_link(&mut self, page: u32, low_link: usize) {
+                let low_link = self.low_links[&page].min(low_link);
+                self.low_links.insert(page, low_link);
+            }
+
+            fn leave(&mut self, page: u32) {
+                if let Some(&(parent, _)) = self.visiting.last() {
+                    self.lower_low_link(parent, self.low_links[&page]);
+                }
+                if self.low_links[&page] == self.indices[&page] {
+                    let mut component = Vec::new();
+                    while let Some(member) = self.stack.pop() {
+                        self.on_stack.remove(&member);
+                        component.push(member);
+                        if member == page {
+                            break;
+                        }
+                    }
+                    component.sort();
+                    self.components.push(component);
+                }
+            }
+        }
+
+        let mut search = Search {
+            next_index: 0,
+            indices: HashMap::new(),
+            low_links: HashMap::new(),
+            stack: Vec::new(),
+            on_stack: HashSet::new(),
+            components: Vec::new(),
+            visiting: Vec::new(),
+        };
+        for &page in self.edges.keys() {
+            if search.indices.contains_key(&page) {
+                continue;
+            }
+            search.enter(self, page);
+            while let Some((page, next_pages)) = search.visiting.last_mut() {
+                let page = *page;
+                match next_pages.next() {
+                    Some(&next_page) if !search.indices.contains_key(&next_page) => {
+                        search.enter(self, next_page)
+                    }
+                    Some(&next_page) => {
+                        if search.on_stack.contains(&next_page) {
+                            search.lower_low_link(page, search.indices[&next_page]);
+                        }
+                    }
+                    None => {
+                        search.visiting.pop();
+                        search.leave(page);
+                    }
+                }
+            }
+        }
+        search.components
+    }
+
+    fn is_cyclic_component(&self, component: &[u32]) -> bool {
+        component.len() > 1 || self.edges[&component[0]].contains(&component[0])
+    }
+}
+
+#[derive(Serialize)]
+pub struct UpdateSubgraph {
+    pub index: usize,
+    pub pages: Vec<u32>,
+    pub rules: Vec<Rule>,
+    pub violated_rules: Vec<Rule>,
+}
+
+impl UpdateSubgraph {
+    fn new(
+        index: usize,
+        update: &Update,
+        graph: &RuleGraph,
+        rule_map: &HashMap<u32, HashSet<u32>>,
+    ) -> UpdateSubgraph {
+        let positions: HashMap<u32, usize> = update
+            .pages
+            .iter()
+            .enumerate()
+            .map(|(position, &page)| (page, position))
+            .collect();
+        let rules: Vec<Rule> = graph
+            .rules()
+            .filter(|rule| {
+                positions.contains_key(&rule.first) && positions.contains_key(&rule.second)
+            })
+            .collect();
+        let violated_rules = update
+            .violations(rule_map)
+            .into_iter()
+            .map(|violation| violation.rule)
+            .collect();
+        UpdateSubgraph {
+            index,
+            pages: update.pages.clone(),
+            rules,
+            violated_rules,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct GraphReport {
+    pub pages: Vec<u32>,
+    pub rules: Vec<Rule>,
+    // Largest first, a component of one page is only a cycle if the page has a rule to itself.
+    pub strongly_connected_components: Vec<Vec<u32>>,
+    pub unconstrained_pages: Vec<u32>,
+    pub is_acyclic: bool,
+    pub update: Option<UpdateSubgraph>,
+}
+
+impl GraphReport {
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec!["digraph rules {".to_string()];
+        lines.push(format!("    // acyclic: {}", self.is_acyclic));
+        lines.push(format!(
+            "    // strongly connected components: {}",
+            self.strongly_connected_components.len()
+        ));
+        lines.push(format!(
+            "    // unconstrained pages: {:?}",
+            self.unconstrained_pages
+        ));
+
+        let cyclic_components = self
+            .strongly_connected_components
+            .iter()
+            .filter(|component| component.len() > 1);
+        for (index, component) in cyclic_components.enumerate() {
+            lines.push(format!("    subgraph cluster_{} {{", index));
+            lines.push(format!("        label=\"component {}\";", index));
+            for page in component {
+                lines.push(format!("        {};", page));
+            }
+            lines.push("    }".to_string());
+        }
+
+        for page in &self.unconstrained_pages {
+            lines.push(format!("    {} [style=dashed];", page));
+        }
+
+        match &self.update {
+            Some(update) => {
+                lines.push(format!(
+                    "    // update {}: {:?}",
+                    update.index, update.pages
+                ));
+                for rule in &update.violated_rules {
+                    lines.push(format!("    // violated: {}|{}", rule.first, rule.second));
+                }
+                for page in &update.pages {
+                    lines.push(format!("    {} [style=filled, fillcolor=lightblue];", page));
+                }
+                for rule in &self.rules {
+                    let attributes = if update.violated_rules.contains(rule) {
+                        " [color=red, penwidth=2]"
+                    } else if update.rules.contains(rule) {
+                        " [penwidth=2]"
+                    } else {
+                        " [color=gray]"
+                    };
+                    lines.push(format!(
+                        "    {} -> {}{};",
+                        rule.first, rule.second, attributes
+                    ));
+                }
+            }
+            None => {
+                for rule in &self.rules {
+                    lines.push(format!("    {} -> {};", rule.first, rule.second));
+                }
+            }
+        }
+
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+}
+
+pub fn graph_report(
+    rules: &[Rule],
+    updates: &[Update],
+    update_index: Option<usize>,
+) -> Result<GraphReport, io::Error> {
+    let graph = RuleGraph::new(rules);
+    let mut strongly_connected_components = graph.strongly_connected_components();
+    let is_acyclic = !strongly_connected_components
+        .iter()
+        .any(|component| graph.is_cyclic_component(component));
+    strongly_connected_components.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+
+    let unconstrained_pages: BTreeSet<u32> = updates
+        .iter()
+        .flat_map(|update| update.pages.iter().cloned())
+        .filter(|page| !graph.edges.contains_key(page))
+        .collect();
+
+    let update = match update_index {
+        Some(index) => match updates.get(index) {
+            Some(update) => Some(UpdateSubgraph::new(index, update, &graph, &rule_map(rules))),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Update index {} out of range, there are {} updates",
+                        index,
+                        updates.len()
+                    ),
+                ))
+            }
+        },
+        None => None,
+    };
+
+    Ok(GraphReport {
+        pages: graph.edges.keys().cloned().collect(),
+        rules: graph.rules().collect(),
+        strongly_connected_components,
+        unconstrained_pages: unconstrained_pages.into_iter().collect(),
+        is_acyclic,
+        update,
+    })
+}
+
+fn graph(
+    rules: &[Rule],
+    updates: &[Update],
+    format: GraphFormat,
+    update_index: Option<usize>,
+) -> Result<(), io::Error> {
+    let report = graph_report(rules, updates, update_index)?;
+    match format {
+        GraphFormat::Dot => println!("{}", report.to_dot()),
+        GraphFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
     Ok(())
 }
@@ -1,19 +1,32 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::fs::read_to_string;
 use std::io;
 use std::iter::Extend;
+use tracing::{debug, instrument, trace};
+
+use crate::input::read_input;
+use crate::parsing::{grid, parse_all};
 
 pub fn handle(input_file: std::path::PathBuf, part_number: u8) -> Result<(), io::Error> {
-    let contents = read_to_string(input_file)?;
-    match part_number {
-        1 => part_1(&contents),
-        2 => part_2(&contents),
-        _ => Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            "Invalid part number",
-        )),
-    }
+    let contents = read_input(&input_file)?;
+    let map = parse(&contents)?;
+    let answer = match part_number {
+        1 => part_1(&map)?,
+        2 => part_2(&map)?,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid part number",
+            ))
+        }
+    };
+    println!("{:?}", answer);
+    Ok(())
+}
+
+#[instrument(skip_all)]
+pub fn parse(contents: &str) -> Result<Map, io::Error> {
+    Map::new(contents)
 }
 
 #[derive(Clone, Hash, Copy, Debug, Eq, PartialEq)]
@@ -69,43 +82,40 @@ enum MovementEnd {
     OffBoard(Vec<Position>),
 }
 
-struct Map {
+#[derive(Clone)]
+pub struct Map {
     locations: Vec<Vec<char>>,
     guard_start_position: Position,
-    guard_position: Option<Position>,
+    // Where the guard is until it leaves, only walks that end on the map move it.
+    guard_position: Position,
     direction: GuardDirection,
 }
 
 impl Map {
-    fn new(contents: &str) -> Map {
-        let locations = contents
-            .split("\n")
+    fn new(contents: &str) -> Result<Map, io::Error> {
+        let locations: Vec<Vec<char>> = parse_all(contents, "a map", grid)?
+            .iter()
             .map(|line| line.chars().collect())
             .collect();
-        Map::new_from_locations(locations)
-    }
-
-    fn new_from_locations(locations: Vec<Vec<char>>) -> Map {
-        // println!("{:?}", locations);
-        let filtered_locations: Vec<Vec<char>> = locations
-            .into_iter()
-            .filter(|line| !line.is_empty())
-            .collect();
-        for (y, line) in filtered_locations.iter().enumerate() {
+        for (y, line) in locations.iter().enumerate() {
             if let Some(x) = line.iter().position(|&c| c == '^') {
                 let guard_start_position = Position {
                     x: x as i32,
                     y: y as i32,
                 };
-                return Map {
-                    locations: filtered_locations,
+                debug!(rows = locations.len(), guard = ?guard_start_position, "Found the guard");
+                return Ok(Map {
+                    locations,
                     guard_start_position,
-                    guard_position: Some(guard_start_position),
+                    guard_position: guard_start_position,
                     direction: GuardDirection::Up,
-                };
+                });
             }
         }
-        panic!("No guard start position found");
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "The map has no guard, marked with ^",
+        ))
     }
 
     fn updated_with_obstruction(original: &Map, position: Position) -> Option<Map> {
@@ -113,43 +123,38 @@ impl Map {
             return None;
         }
 
-        let mut new_locations = original.locations.clone();
-        new_locations[position.y as usize][position.x as usize] = '#';
-        Some(Map::new_from_locations(new_locations))
+        let mut map = original.clone();
+        map.locations[position.y as usize][position.x as usize] = '#';
+        map.guard_position = map.guard_start_position;
+        map.direction = GuardDirection::Up;
+        Some(map)
     }
 }
 
 impl Map {
     fn character_at(&self, position: &Position) -> char {
-        // println!("{:?}", position);
         self.locations[position.y as usize][position.x as usize]
     }
 
+    // Lines may have different lengths, anything past the end of a line is off the board.
     fn is_on_board(&self, position: &Position) -> bool {
-        // println!("pos {:?} bounds {:?} {:?}", position, self.locations[0].len(), self.locations.len());
-        position.x >= 0
-            && position.x < self.locations[0].len() as i32
-            && position.y >= 0
+        position.y >= 0
             && position.y < self.locations.len() as i32
+            && position.x >= 0
+            && position.x < self.locations[position.y as usize].len() as i32
     }
 
     fn guard_positions(&self, direction: &GuardDirection) -> MovementEnd {
         let mut positions: Vec<Position> = Vec::new();
-
-        match &self.guard_position {
-            Some(guard_position) => {
-                let mut next_position = *guard_position;
-                while self.is_on_board(&next_position) && self.character_at(&next_position) != '#' {
-                    positions.push(next_position);
-                    next_position = next_position.next(direction);
-                }
-                if self.is_on_board(&next_position) {
-                    MovementEnd::OnBoard(positions)
-                } else {
-                    MovementEnd::OffBoard(positions)
-                }
-            }
-            None => panic!("Guard position not found"),
+        let mut next_position = self.guard_position;
+        while self.is_on_board(&next_position) && self.character_at(&next_position) != '#' {
+            positions.push(next_position);
+            next_position = next_position.next(direction);
+        }
+        if self.is_on_board(&next_position) {
+            MovementEnd::OnBoard(positions)
+        } else {
+            MovementEnd::OffBoard(positions)
         }
     }
 
@@ -157,12 +162,10 @@ impl Map {
         let movement = self.guard_positions(&self.direction);
         match &movement {
             MovementEnd::OnBoard(positions) => {
-                self.guard_position = Some(*positions.last().unwrap());
+                self.guard_position = *positions.last().unwrap();
                 self.direction = self.direction.next();
             }
-            MovementEnd::OffBoard(_) => {
-                self.guard_position = None;
-            }
+            MovementEnd::OffBoard(_) => {}
         }
         movement
     }
@@ -191,55 +194,62 @@ impl Map {
         }
         false
     }
-}
 
-fn part_1(contents: &str) -> Result<(), io::Error> {
-    let mut map = Map::new(contents);
-    let mut visited_positions: HashSet<Position> = HashSet::new();
-    loop {
-        let movement = map.move_guard();
-        match movement {
-            MovementEnd::OnBoard(positions) => {
-                visited_positions.extend(positions.clone());
-                // println!("on board {:?}", positions);
-            }
-            MovementEnd::OffBoard(positions) => {
-                visited_positions.extend(positions.clone());
-                // println!("off board {:?}", positions);
-                break;
+    // Every position the guard walks through before leaving the map, or an error if it walks in
+    // a loop instead, found the same way as `path_contains_loop` during the one walk.
+    fn visited_positions(&self) -> Result<HashSet<Position>, io::Error> {
+        let mut map = self.clone();
+        let mut visited_positions: HashSet<Position> = HashSet::new();
+        let mut directions_at_obstructions: HashMap<Position, HashSet<GuardDirection>> =
+            HashMap::new();
+        loop {
+            match map.move_guard() {
+                MovementEnd::OnBoard(positions) => {
+                    trace!(?positions, "Turned at an obstruction");
+                    let obstruction = map.guard_position.next(&map.direction);
+                    visited_positions.extend(positions);
+                    if !directions_at_obstructions
+                        .entry(obstruction)
+                        .or_default()
+                        .insert(map.direction.clone())
+                    {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "The guard walks in a loop and never leaves the map",
+                        ));
+                    }
+                }
+                MovementEnd::OffBoard(positions) => {
+                    trace!(?positions, "Left the map");
+                    visited_positions.extend(positions);
+                    return Ok(visited_positions);
+                }
             }
         }
     }
-    println!("{:?}", visited_positions.len());
-    Ok(())
 }
 
-fn part_2(contents: &str) -> Result<(), io::Error> {
-    let mut map = Map::new(contents);
-    let mut visited_positions: HashSet<Position> = HashSet::new();
-    loop {
-        let movement = map.move_guard();
-        match movement {
-            MovementEnd::OnBoard(positions) => {
-                visited_positions.extend(positions.clone());
-                // println!("on board {:?}", positions);
-            }
-            MovementEnd::OffBoard(positions) => {
-                visited_positions.extend(positions.clone());
-                // println!("off board {:?}", positions);
-                break;
-            }
-        }
-    }
+#[instrument(skip_all)]
+pub fn part_1(map: &Map) -> Result<usize, io::Error> {
+    Ok(map.visited_positions()?.len())
+}
 
+#[instrument(skip_all)]
+pub fn part_2(map: &Map) -> Result<usize, io::Error> {
+    let visited_positions = map.visited_positions()?;
+
+    debug!(
+        candidates = visited_positions.len(),
+        "Trying an obstruction on every visited position"
+    );
     let mut count_of_obstructions = 0;
     for position in visited_positions {
-        if let Some(mut updated_map) = Map::updated_with_obstruction(&map, position) {
+        if let Some(mut updated_map) = Map::updated_with_obstruction(map, position) {
             if updated_map.path_contains_loop() {
+                trace!(?position, "An obstruction here makes a loop");
                 count_of_obstructions += 1;
             }
         }
     }
-    println!("{:?}", count_of_obstructions);
-    Ok(())
+    Ok(count_of_obstructions)
 }
@@ -1,24 +1,28 @@
-use nom::{
-    bytes::complete::tag,
-    character::complete::{digit1, newline, space1},
-    combinator::map_res,
-    multi::separated_list1,
-    sequence::separated_pair,
-    IResult,
-};
-use std::fs::read_to_string;
 use std::io;
+use tracing::{debug, instrument};
+
+use crate::input::read_input;
+use crate::parsing::{key_values, lines, parse_all, unsigned, whitespace_separated, ParseResult};
 
 pub fn handle(input_file: std::path::PathBuf, part_number: u8) -> Result<(), io::Error> {
-    let contents = read_to_string(input_file)?;
-    match part_number {
-        1 => part_1(&contents),
-        2 => part_2(&contents),
-        _ => Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            "Invalid part number",
-        )),
-    }
+    let contents = read_input(&input_file)?;
+    let equations = parse(&contents)?;
+    let operations = match part_number {
+        1 => PART_1_OPERATIONS,
+        2 => PART_2_OPERATIONS,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid part number",
+            ))
+        }
+    };
+    // Only the answer goes to stdout, the count of valid equations is logged at debug level.
+    println!(
+        "{}",
+        sum_of_test_values(&valid_equations(&equations, operations))
+    );
+    Ok(())
 }
 
 enum Operation {
@@ -28,19 +32,19 @@ enum Operation {
 }
 
 impl Operation {
-    fn apply(&self, lhs: i64, rhs: i64) -> i64 {
+    // Returns None when the result doesn't fit in an i64, which is then too large for any test
+    // value.
+    fn apply(&self, lhs: i64, rhs: i64) -> Option<i64> {
         match self {
-            Operation::Addition => lhs + rhs,
-            Operation::Multiplication => lhs * rhs,
-            Operation::Concatenation => {
-                (lhs.to_string() + &rhs.to_string()).parse::<i64>().unwrap()
-            }
+            Operation::Addition => lhs.checked_add(rhs),
+            Operation::Multiplication => lhs.checked_mul(rhs),
+            Operation::Concatenation => (lhs.to_string() + &rhs.to_string()).parse::<i64>().ok(),
         }
     }
 }
 
 #[derive(Debug, PartialEq)]
-struct Equation {
+pub struct Equation {
     test_value: i64,
     factors: Vec<i64>,
 }
@@ -61,7 +65,10 @@ impl Equation {
             let mut tail = factors.to_owned();
             let head = tail.drain(0..2).collect::<Vec<i64>>();
             for operation in operations {
-                let mut updated_factors = [operation.apply(head[0], head[1])].to_vec();
+                let Some(combined) = operation.apply(head[0], head[1]) else {
+                    continue;
+                };
+                let mut updated_factors = [combined].to_vec();
                 updated_factors.extend(&mut tail.iter().cloned());
                 if check_factors(&updated_factors, test_value, operations) {
                     return true;
@@ -73,17 +80,9 @@ impl Equation {
     }
 }
 
-fn str_to_i64(input: &str) -> IResult<&str, i64> {
-    map_res(digit1, str::parse::<i64>)(input)
-}
-
-fn parse_factors(input: &str) -> IResult<&str, Vec<i64>> {
-    separated_list1(space1, str_to_i64)(input)
-}
-
-fn parse_equation(input: &str) -> IResult<&str, Equation> {
+fn parse_equation(input: &str) -> ParseResult<'_, Equation> {
     let (input, (test_value, factors)) =
-        separated_pair(str_to_i64, tag(": "), parse_factors)(input)?;
+        key_values(unsigned, whitespace_separated(unsigned))(input)?;
     Ok((
         input,
         Equation {
@@ -93,36 +92,50 @@ fn parse_equation(input: &str) -> IResult<&str, Equation> {
     ))
 }
 
-fn parse_equations(input: &str) -> Vec<Equation> {
-    separated_list1(newline, parse_equation)(input).unwrap().1
+#[instrument(skip_all)]
+pub fn parse(contents: &str) -> Result<Vec<Equation>, io::Error> {
+    parse_all(contents, "a list of equations", lines(parse_equation))
 }
 
-fn part_1(contents: &str) -> Result<(), io::Error> {
-    let equations = parse_equations(contents);
+const PART_1_OPERATIONS: &[Operation] = &[Operation::Addition, Operation::Multiplication];
+const PART_2_OPERATIONS: &[Operation] = &[
+    Operation::Addition,
+    Operation::Multiplication,
+    Operation::Concatenation,
+];
+
+fn valid_equations<'a>(equations: &'a [Equation], operations: &[Operation]) -> Vec<&'a Equation> {
     let valid_equations = equations
         .iter()
-        .filter(|e| e.is_valid(&[Operation::Addition, Operation::Multiplication]))
+        .filter(|e| e.is_valid(operations))
         .collect::<Vec<&Equation>>();
-    println!("{}", valid_equations.len());
-    let sum_of_valid_equations = valid_equations.iter().fold(0, |acc, e| acc + e.test_value);
-    println!("{:?}", sum_of_valid_equations);
-    Ok(())
+    debug!(
+        valid = valid_equations.len(),
+        total = equations.len(),
+        "Checked the equations"
+    );
+    valid_equations
 }
 
-fn part_2(contents: &str) -> Result<(), io::Error> {
-    let equations = parse_equations(contents);
-    let valid_equations = equations
+// Summed as an i128 since enough test values close to the limit of an i64 would overflow it.
+fn sum_of_test_values(equations: &[&Equation]) -> i128 {
+    equations
         .iter()
-        .filter(|e| {
-            e.is_valid(&[
-                Operation::Addition,
-                Operation::Multiplication,
-                Operation::Concatenation,
-            ])
-        })
-        .collect::<Vec<&Equation>>();
-    println!("{}", valid_equations.len());
-    let sum_of_valid_equations = valid_equations.iter().fold(0, |acc, e| acc + e.test_value);
-    println!("{:?}", sum_of_valid_equations);
-    Ok(())
+        .fold(0, |acc, e| acc + e.test_value as i128)
+}
+
+#[instrument(skip_all)]
+pub fn part_1(equations: &[Equation]) -> Result<i128, io::Error> {
+    Ok(sum_of_test_values(&valid_equations(
+        equations,
+        PART_1_OPERATIONS,
+    )))
+}
+
+#[instrument(skip_all)]
+pub fn part_2(equations: &[Equation]) -> Result<i128, io::Error> {
+    Ok(sum_of_test_values(&valid_equations(
+        equations,
+        PART_2_OPERATIONS,
+    )))
 }
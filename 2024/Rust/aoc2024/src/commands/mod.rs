@@ -5,3 +5,133 @@ pub mod day4;
 pub mod day5;
 pub mod day6;
 pub mod day7;
+
+use std::fmt::Display;
+use std::io::{self, Read};
+use std::time::{Duration, Instant};
+use tracing::info_span;
+
+use crate::input::normalize;
+
+pub struct Solution {
+    pub answer: String,
+    // Zero when the input is streamed, since parsing and solving then happen together.
+    pub parse_time: Duration,
+    pub solve_time: Duration,
+}
+
+// Solves a part reading the input as it goes. Day 3 streams it so it is never held in memory,
+// the other days read it all and normalise it first.
+pub fn solve_reader(
+    day: u8,
+    mut reader: impl Read,
+    part_number: u8,
+) -> Result<Solution, io::Error> {
+    if day == 3 {
+        let _span = info_span!("day", day).entered();
+        let instructions = day3::instructions(part_number)?;
+        let start = Instant::now();
+        let state = day3::run(reader, instructions)?;
+        return Ok(Solution {
+            answer: state.sum.to_string(),
+            parse_time: Duration::ZERO,
+            solve_time: start.elapsed(),
+        });
+    }
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents)?;
+    solve(day, &normalize(&contents), part_number)
+}
+
+// Solves a part with the puzzle's own rules, without any of the day specific options.
+pub fn solve(day: u8, contents: &str, part_number: u8) -> Result<Solution, io::Error> {
+    let _span = info_span!("day", day).entered();
+    match day {
+        1 => timed(
+            contents,
+            part_number,
+            day1::parse,
+            day1::part_1,
+            day1::part_2,
+        ),
+        2 => timed(
+            contents,
+            part_number,
+            day2::parse,
+            |reports| day2::part_1(reports),
+            |reports| day2::part_2(reports),
+        ),
+        3 => timed(
+            contents,
+            part_number,
+            day3::parse,
+            |tokens| day3::part_1(tokens),
+            |tokens| day3::part_2(tokens),
+        ),
+        4 => timed(
+            contents,
+            part_number,
+            day4::parse,
+            day4::part_1,
+            day4::part_2,
+        ),
+        5 => timed(
+            contents,
+            part_number,
+            day5::parse,
+            |(rules, updates)| day5::part_1(rules, updates),
+            |(rules, updates)| day5::part_2(rules, updates),
+        ),
+        6 => timed(
+            contents,
+            part_number,
+            day6::parse,
+            day6::part_1,
+            day6::part_2,
+        ),
+        7 => timed(
+            contents,
+            part_number,
+            day7::parse,
+            |equations| day7::part_1(equations),
+            |equations| day7::part_2(equations),
+        ),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Day {} has not been solved", day),
+        )),
+    }
+}
+
+fn timed<T, A: Display>(
+    contents: &str,
+    part_number: u8,
+    parse: impl Fn(&str) -> Result<T, io::Error>,
+    part_1: impl Fn(&T) -> Result<A, io::Error>,
+    part_2: impl Fn(&T) -> Result<A, io::Error>,
+) -> Result<Solution, io::Error> {
+    if !(1..=2).contains(&part_number) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Invalid part number",
+        ));
+    }
+
+    let start = Instant::now();
+    let input = parse(contents)?;
+    let parse_time = start.elapsed();
+
+    let start = Instant::now();
+    let answer = if part_number == 1 {
+        part_1(&input)?
+    } else {
+        part_2(&input)?
+    };
+    let solve_time = start.elapsed();
+
+    Ok(Solution {
+        answer: answer.to_string(),
+        parse_time,
+        solve_time,
+    })
+}
@@ -0,0 +1,42 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use tracing::info;
+
+use crate::args::SiteArgs;
+use crate::site::{day_path, Site};
+
+// Inputs are cached under the same names as the ones downloaded by hand, `dayN-1.txt`.
+pub fn cached_input(site: &Site, day: u8) -> PathBuf {
+    site.problems.join(format!("day{}-1.txt", day))
+}
+
+// Returns where the input is, only downloading it when it is not cached yet.
+pub fn fetch(site: &Site, day: u8) -> Result<PathBuf, io::Error> {
+    if !(1..=25).contains(&day) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("There is no day {}", day),
+        ));
+    }
+    let path = cached_input(site, day);
+    if path.exists() {
+        info!("Using the cached input {}", path.display());
+        return Ok(path);
+    }
+
+    let input = site.get(&format!("{}/input", day_path(day)))?;
+    fs::create_dir_all(&site.problems)?;
+    // Written next to the final file and renamed, so an interrupted download is never cached.
+    let partial = path.with_extension("txt.partial");
+    fs::write(&partial, input)?;
+    fs::rename(&partial, &path)?;
+    info!("Downloaded {}", path.display());
+    Ok(path)
+}
+
+pub fn handle(day: u8, site: SiteArgs) -> Result<(), io::Error> {
+    let site = Site::from_args(&site)?;
+    println!("{}", fetch(&site, day)?.display());
+    Ok(())
+}
@@ -0,0 +1,462 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+// SplitMix64, kept here rather than pulled in from a crate so a seed always produces the same
+// input whatever version of a dependency is used.
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // A number in `low..=high`.
+    fn between(&mut self, low: u64, high: u64) -> u64 {
+        let range = high - low + 1;
+        low + ((self.next_u64() as u128 * range as u128) >> 64) as u64
+    }
+
+    fn index(&mut self, length: usize) -> usize {
+        self.between(0, length as u64 - 1) as usize
+    }
+
+    // True roughly once every `times` calls.
+    fn one_in(&mut self, times: u64) -> bool {
+        self.between(1, times) == 1
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.index(i + 1));
+        }
+    }
+}
+
+pub struct Generated {
+    pub input: String,
+    // The answers for parts 1 and 2, when the generator knows them.
+    pub answers: [Option<String>; 2],
+}
+
+// The size is the number of lines for days 1, 2 and 7, of instructions for day 3, of updates for
+// day 5 and the side of the grid for days 4 and 6. Each default is close to a real input.
+fn default_size(day: u8) -> usize {
+    match day {
+        1 | 2 => 1000,
+        3 => 750,
+        4 => 140,
+        5 => 200,
+        6 => 130,
+        _ => 850,
+    }
+}
+
+pub fn generate(day: u8, seed: u64, size: Option<usize>) -> Result<Generated, io::Error> {
+    let mut rng = Rng::new(seed);
+    let size = size.unwrap_or(default_size(day));
+    if size == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The size must be at least 1",
+        ));
+    }
+    match day {
+        1 => Ok(day1(&mut rng, size)),
+        2 => Ok(day2(&mut rng, size)),
+        3 => Ok(day3(&mut rng, size)),
+        4 => Ok(day4(&mut rng, size)),
+        5 => Ok(day5(&mut rng, size)),
+        6 => day6(&mut rng, size),
+        7 => Ok(day7(&mut rng, size)),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Day {} has no generator", day),
+        )),
+    }
+}
+
+pub fn handle(
+    day: u8,
+    seed: u64,
+    size: Option<usize>,
+    output: Option<PathBuf>,
+    answers: bool,
+) -> Result<(), io::Error> {
+    let generated = generate(day, seed, size)?;
+    match output {
+        Some(path) => fs::write(path, &generated.input)?,
+        None => print!("{}", generated.input),
+    }
+    // The answers go to stderr so the input can be piped or redirected on its own.
+    if answers {
+        for (part, answer) in generated.answers.iter().enumerate() {
+            match answer {
+                Some(answer) => eprintln!("Part {}: {}", part + 1, answer),
+                None => eprintln!("Part {}: unknown", part + 1),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn day1(rng: &mut Rng, size: usize) -> Generated {
+    // Drawing the right list partly from the left one makes the similarity score non-zero.
+    let left_ids: Vec<u64> = (0..size).map(|_| rng.between(10000, 99999)).collect();
+    let right_ids: Vec<u64> = (0..size)
+        .map(|_| {
+            if rng.one_in(3) {
+                left_ids[rng.index(size)]
+            } else {
+                rng.between(10000, 99999)
+            }
+        })
+        .collect();
+
+    let input = left_ids
+        .iter()
+        .zip(&right_ids)
+        .map(|(left, right)| format!("{}   {}\n", left, right))
+        .collect();
+
+    let mut sorted_left = left_ids.clone();
+    let mut sorted_right = right_ids.clone();
+    sorted_left.sort();
+    sorted_right.sort();
+    let distance: u64 = sorted_left
+        .iter()
+        .zip(&sorted_right)
+        .map(|(left, right)| left.abs_diff(*right))
+        .sum();
+    let similarity: u64 = left_ids
+        .iter()
+        .map(|left| left * right_ids.iter().filter(|right| *right == left).count() as u64)
+        .sum();
+    Generated {
+        input,
+        answers: [Some(distance.to_string()), Some(similarity.to_string())],
+    }
+}
+
+fn is_safe(levels: &[i64]) -> bool {
+    let steps: Vec<i64> = levels.windows(2).map(|pair| pair[1] - pair[0]).collect();
+    steps.iter().all(|step| (1..=3).contains(step))
+        || steps.iter().all(|step| (-3..=-1).contains(step))
+}
+
+fn day2(rng: &mut Rng, size: usize) -> Generated {
+    let mut reports = Vec::with_capacity(size);
+    for _ in 0..size {
+        let length = rng.between(5, 8) as usize;
+        let direction = if rng.one_in(2) { 1 } else { -1 };
+        let mut levels = vec![rng.between(10, 90) as i64];
+        for _ in 1..length {
+            let step = rng.between(1, 3) as i64 * direction;
+            levels.push(levels.last().unwrap() + step);
+        }
+        // Most reports get one or two bad levels, so all of safe, dampened and unsafe show up.
+        for _ in 0..rng.between(0, 2) {
+            let index = rng.index(length);
+            levels[index] += rng.between(1, 6) as i64 - 3;
+        }
+        reports.push(levels);
+    }
+
+    let input = reports
+        .iter()
+        .map(|levels| {
+            let levels: Vec<String> = levels.iter().map(|level| level.to_string()).collect();
+            levels.join(" ") + "\n"
+        })
+        .collect();
+
+    let safe = reports.iter().filter(|levels| is_safe(levels)).count();
+    let dampened = reports
+        .iter()
+        .filter(|levels| {
+            (0..levels.len()).any(|index| {
+                let mut remaining = levels.to_vec();
+                remaining.remove(index);
+                is_safe(&remaining)
+            })
+        })
+        .count();
+    Generated {
+        input,
+        answers: [Some(safe.to_string()), Some(dampened.to_string())],
+    }
+}
+
+// Junk never uses a letter, digit or bracket from an instruction, so junk next to an instruction
+// or near miss can't accidentally complete one and the answers stay known.
+const JUNK: &[u8] = b" !@#$%^&*-+=[]{}<>?/;:~'\"xyzXYZ";
+const NEAR_MISSES: &[&str] = &[
+    "mul(4*",
+    "mul[3,7]",
+    "mul ( 2 , 4 )",
+    "mul(1234,5)",
+    "mul(6,7",
+    "don't",
+    "do_not()",
+    "why()",
+];
+
+fn day3(rng: &mut Rng, size: usize) -> Generated {
+    let mut input = String::new();
+    let mut is_enabled = true;
+    let mut sums = [0, 0];
+    for index in 0..size {
+        for _ in 0..rng.between(0, 8) {
+            input.push(JUNK[rng.index(JUNK.len())] as char);
+        }
+        if rng.one_in(6) {
+            input.push_str(NEAR_MISSES[rng.index(NEAR_MISSES.len())]);
+        }
+        if rng.one_in(8) {
+            is_enabled = rng.one_in(2);
+            input.push_str(if is_enabled { "do()" } else { "don't()" });
+        } else {
+            let a = rng.between(1, 999);
+            let b = rng.between(1, 999);
+            input.push_str(&format!("mul({},{})", a, b));
+            sums[0] += a * b;
+            if is_enabled {
+                sums[1] += a * b;
+            }
+        }
+        if (index + 1) % 125 == 0 {
+            input.push('\n');
+        }
+    }
+    input.push('\n');
+    Generated {
+        input,
+        answers: sums.map(|sum| Some(sum.to_string())),
+    }
+}
+
+fn day4(rng: &mut Rng, size: usize) -> Generated {
+    let letters = b"XMAS";
+    let input = (0..size)
+        .map(|_| {
+            let line: String = (0..size)
+                .map(|_| letters[rng.index(letters.len())] as char)
+                .collect();
+            line + "\n"
+        })
+        .collect();
+    // Counting needs the whole search, which is the puzzle itself.
+    Generated {
+        input,
+        answers: [None, None],
+    }
+}
+
+fn day5(rng: &mut Rng, size: usize) -> Generated {
+    // A hidden order of the pages with a rule for every pair, so the rules are acyclic and every
+    // update has exactly one correct order.
+    let mut order: Vec<u64> = (10..100).collect();
+    rng.shuffle(&mut order);
+    order.truncate(49);
+    let mut rules: Vec<(u64, u64)> = Vec::new();
+    for i in 0..order.len() {
+        for j in i + 1..order.len() {
+            rules.push((order[i], order[j]));
+        }
+    }
+    rng.shuffle(&mut rules);
+
+    let mut updates = Vec::with_capacity(size);
+    let mut sums = [0, 0];
+    for _ in 0..size {
+        let length = rng.between(2, 11) as usize * 2 + 1;
+        let mut positions: Vec<usize> = (0..order.len()).collect();
+        rng.shuffle(&mut positions);
+        positions.truncate(length);
+        let is_ordered = rng.one_in(2);
+        if is_ordered {
+            positions.sort();
+        }
+        let pages: Vec<u64> = positions.iter().map(|&position| order[position]).collect();
+        if positions.windows(2).all(|pair| pair[0] < pair[1]) {
+            sums[0] += pages[length / 2];
+        } else {
+            positions.sort();
+            sums[1] += order[positions[length / 2]];
+        }
+        updates.push(pages);
+    }
+
+    let mut input = String::new();
+    for (first, second) in &rules {
+        input.push_str(&format!("{}|{}\n", first, second));
+    }
+    input.push('\n');
+    for pages in &updates {
+        let pages: Vec<String> = pages.iter().map(|page| page.to_string()).collect();
+        input.push_str(&(pages.join(",") + "\n"));
+    }
+    Generated {
+        input,
+        answers: sums.map(|sum| Some(sum.to_string())),
+    }
+}
+
+// Walks the guard and returns how many cells it visits, or None if it never leaves.
+fn guard_visits(grid: &[Vec<u8>], start: (usize, usize)) -> Option<usize> {
+    let directions = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+    let (mut x, mut y) = (start.0 as isize, start.1 as isize);
+    let mut direction = 0;
+    let mut states = HashSet::new();
+    let mut visited = HashSet::new();
+    loop {
+        if !states.insert((x, y, direction)) {
+            return None;
+        }
+        visited.insert((x, y));
+        let (dx, dy) = directions[direction];
+        let (next_x, next_y) = (x + dx, y + dy);
+        if next_x < 0 || next_y < 0 {
+            return Some(visited.len());
+        }
+        match grid
+            .get(next_y as usize)
+            .and_then(|line| line.get(next_x as usize))
+        {
+            None => return Some(visited.len()),
+            Some(b'#') => direction = (direction + 1) % 4,
+            Some(_) => (x, y) = (next_x, next_y),
+        }
+    }
+}
+
+// How many random starts day 6 tries before giving up.
+const START_ATTEMPTS: usize = 2000;
+
+fn day6(rng: &mut Rng, size: usize) -> Result<Generated, io::Error> {
+    let mut grid: Vec<Vec<u8>> = (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| if rng.one_in(20) { b'#' } else { b'.' })
+                .collect()
+        })
+        .collect();
+
+    // The guard has to be able to leave for part 1 to have an answer, so look for an open cell it
+    // can leave from, clearing one if the map happens to be full, and give up after a while in
+    // case the obstructions trap it everywhere. Starting away from the edges like the real inputs
+    // gives it a longer walk.
+    let middle = |rng: &mut Rng| size / 4 + rng.index(size.div_ceil(2));
+    let mut found = None;
+    for attempt in 0..START_ATTEMPTS {
+        let start = (middle(rng), middle(rng));
+        if attempt >= START_ATTEMPTS / 2 {
+            grid[start.1][start.0] = b'.';
+        }
+        if grid[start.1][start.0] == b'#' {
+            continue;
+        }
+        if let Some(visited) = guard_visits(&grid, start) {
+            found = Some((start, visited));
+            break;
+        }
+    }
+    let Some((start, visited)) = found else {
+        return Err(io::Error::other(format!(
+            "No start the guard can leave from after {} attempts, try another seed",
+            START_ATTEMPTS
+        )));
+    };
+    grid[start.1][start.0] = b'^';
+
+    let input = grid
+        .iter()
+        .map(|line| String::from_utf8_lossy(line).into_owned() + "\n")
+        .collect();
+    // Part 2 means trying an obstruction on every visited cell, which is the puzzle itself.
+    Ok(Generated {
+        input,
+        answers: [Some(visited.to_string()), None],
+    })
+}
+
+fn concatenate(a: u64, b: u64) -> u64 {
+    a * 10u64.pow(b.to_string().len() as u32) + b
+}
+
+// Whether addition and multiplication alone, applied left to right, can make `test_value`.
+fn can_make_without_concatenation(test_value: u64, factors: &[u64]) -> bool {
+    (0..1u64 << (factors.len() - 1)).any(|operators| {
+        let mut value = factors[0];
+        for (index, &factor) in factors[1..].iter().enumerate() {
+            value = if operators >> index & 1 == 0 {
+                value + factor
+            } else {
+                value * factor
+            };
+        }
+        value == test_value
+    })
+}
+
+fn day7(rng: &mut Rng, size: usize) -> Generated {
+    // Every intermediate value is at most all the factors concatenated, so limiting their digits
+    // keeps every operation, and the sum of every test value, inside an i64.
+    let digit_limit = 18usize.saturating_sub(size.to_string().len()).max(3);
+    let mut input = String::new();
+    let mut sums = [0, 0];
+    for _ in 0..size {
+        let mut factors = vec![rng.between(1, 999)];
+        let mut digits = factors[0].to_string().len();
+        for _ in 1..rng.between(2, 12) {
+            let factor = rng.between(1, 999);
+            digits += factor.to_string().len();
+            if digits > digit_limit {
+                break;
+            }
+            factors.push(factor);
+        }
+        let largest = factors[1..]
+            .iter()
+            .fold(factors[0], |value, &factor| concatenate(value, factor));
+
+        // A third of the equations use only addition and multiplication, a third also
+        // concatenation and the rest ask for more than the factors can ever make.
+        let kind = rng.between(0, 2);
+        let test_value = if kind == 2 {
+            largest + rng.between(1, 1000)
+        } else {
+            factors[1..].iter().fold(factors[0], |value, &factor| {
+                match rng.between(0, if kind == 0 { 1 } else { 2 }) {
+                    0 => value + factor,
+                    1 => value * factor,
+                    _ => concatenate(value, factor),
+                }
+            })
+        };
+        if kind != 2 {
+            sums[1] += test_value;
+            if kind == 0 || can_make_without_concatenation(test_value, &factors) {
+                sums[0] += test_value;
+            }
+        }
+
+        let factors: Vec<String> = factors.iter().map(|factor| factor.to_string()).collect();
+        input.push_str(&format!("{}: {}\n", test_value, factors.join(" ")));
+    }
+    Generated {
+        input,
+        answers: sums.map(|sum| Some(sum.to_string())),
+    }
+}
@@ -0,0 +1,41 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+// Every day parses the normalised input, so they all accept the same variations: a byte order
+// mark, CRLF line endings, whitespace at the end of a line and any number of blank lines at the
+// ends of the file or between sections. The result has `\n` line endings, exactly one blank line
+// between sections and a single final newline.
+pub fn normalize(contents: &str) -> String {
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+    let mut normalized = String::with_capacity(contents.len() + 1);
+    let mut is_after_blank_line = false;
+    for line in contents.lines().map(str::trim_end) {
+        if line.is_empty() {
+            is_after_blank_line = !normalized.is_empty();
+            continue;
+        }
+        if is_after_blank_line {
+            normalized.push('\n');
+            is_after_blank_line = false;
+        }
+        normalized.push_str(line);
+        normalized.push('\n');
+    }
+    normalized
+}
+
+// An input file, or stdin when the path is `-`.
+pub fn open(path: &Path) -> Result<Box<dyn Read>, io::Error> {
+    if path == Path::new("-") {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(File::open(path)?))
+    }
+}
+
+pub fn read_input(path: &Path) -> Result<String, io::Error> {
+    let mut contents = String::new();
+    open(path)?.read_to_string(&mut contents)?;
+    Ok(normalize(&contents))
+}
@@ -0,0 +1,340 @@
+use serde::Deserialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs::{self, read_to_string};
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+
+use crate::answers::YEAR;
+use crate::args::{LeaderboardFormat, SiteArgs};
+use crate::site::Site;
+
+// The site asks for private leaderboards to be downloaded at most once every 15 minutes.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+// The JSON export of a private leaderboard, only the fields used here.
+#[derive(Deserialize)]
+pub struct Leaderboard {
+    pub event: String,
+    pub members: BTreeMap<String, Member>,
+}
+
+#[derive(Deserialize)]
+pub struct Member {
+    pub id: u64,
+    pub name: Option<String>,
+    pub stars: u32,
+    pub local_score: u64,
+    // Day, then part, to when the star was earned.
+    #[serde(default)]
+    pub completion_day_level: BTreeMap<u8, BTreeMap<u8, Star>>,
+}
+
+#[derive(Deserialize)]
+pub struct Star {
+    pub get_star_ts: u64,
+    // Breaks ties between stars earned in the same second.
+    #[serde(default)]
+    pub star_index: u64,
+}
+
+impl Member {
+    fn display_name(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("(anonymous user #{})", self.id),
+        }
+    }
+
+    fn star(&self, day: u8, part: u8) -> Option<&Star> {
+        self.completion_day_level.get(&day)?.get(&part)
+    }
+}
+
+pub fn parse(contents: &str) -> Result<Leaderboard, io::Error> {
+    serde_json::from_str(contents).map_err(|error| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("The input is not a leaderboard export: {}", error),
+        )
+    })
+}
+
+// Every star is worth as many points as there are members for the first to earn it, one less for
+// the second and so on. The points are per member and day.
+fn points(leaderboard: &Leaderboard) -> BTreeMap<(u64, u8), u64> {
+    let member_count = leaderboard.members.len() as u64;
+    let mut points = BTreeMap::new();
+    for day in 1..=25 {
+        for part in 1..=2 {
+            let mut stars: Vec<(u64, u64, u64)> = leaderboard
+                .members
+                .values()
+                .filter_map(|member| {
+                    let star = member.star(day, part)?;
+                    Some((star.get_star_ts, star.star_index, member.id))
+                })
+                .collect();
+            stars.sort();
+            for (place, (_, _, id)) in stars.into_iter().enumerate() {
+                *points.entry((id, day)).or_default() += member_count - place as u64;
+            }
+        }
+    }
+    points
+}
+
+// The local score of every member, recomputed from their stars.
+pub fn local_scores(leaderboard: &Leaderboard) -> BTreeMap<u64, u64> {
+    let mut scores: BTreeMap<u64, u64> = leaderboard
+        .members
+        .values()
+        .map(|member| (member.id, 0))
+        .collect();
+    for ((id, _), points) in points(leaderboard) {
+        *scores.entry(id).or_default() += points;
+    }
+    scores
+}
+
+// Puzzles unlock at midnight in UTC-5.
+fn unlock_time(year: i64, day: u8) -> u64 {
+    // Days since 1970-01-01 of December 1st, from Howard Hinnant's `days_from_civil`.
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * 9 + 2) / 5;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let december_first = era * 146097 + day_of_era - 719468;
+    ((december_first + day as i64 - 1) * 86400 + 5 * 3600) as u64
+}
+
+fn format_duration(seconds: u64) -> String {
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+struct Table {
+    title: String,
+    headers: Vec<&'static str>,
+    // Columns after these are numbers and are right aligned.
+    text_columns: usize,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn render(&self, format: LeaderboardFormat) -> String {
+        let mut output = String::new();
+        match format {
+            LeaderboardFormat::Table => {
+                let widths: Vec<usize> = (0..self.headers.len())
+                    .map(|column| {
+                        self.rows
+                            .iter()
+                            .map(|row| row[column].chars().count())
+                            .chain([self.headers[column].len()])
+                            .max()
+                            .unwrap_or(0)
+                    })
+                    .collect();
+                let line = |cells: Vec<&str>| {
+                    let cells: Vec<String> = cells
+                        .iter()
+                        .zip(&widths)
+                        .enumerate()
+                        .map(|(column, (cell, width))| {
+                            if column < self.text_columns {
+                                format!("{:<width$}", cell, width = width)
+                            } else {
+                                format!("{:>width$}", cell, width = width)
+                            }
+                        })
+                        .collect();
+                    cells.join("  ").trim_end().to_string() + "\n"
+                };
+                output.push_str(&self.title);
+                output.push('\n');
+                output.push_str(&line(self.headers.clone()));
+                for row in &self.rows {
+                    output.push_str(&line(row.iter().map(|cell| cell.as_str()).collect()));
+                }
+            }
+            LeaderboardFormat::Markdown => {
+                let line = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+                output.push_str(&format!("## {}\n\n", self.title));
+                output.push_str(&line(
+                    self.headers
+                        .iter()
+                        .map(|header| header.to_string())
+                        .collect(),
+                ));
+                output.push_str(&line(
+                    (0..self.headers.len())
+                        .map(|column| {
+                            if column < self.text_columns {
+                                "---".to_string()
+                            } else {
+                                "---:".to_string()
+                            }
+                        })
+                        .collect(),
+                ));
+                for row in &self.rows {
+                    output.push_str(&line(
+                        row.iter().map(|cell| cell.replace('|', "\\|")).collect(),
+                    ));
+                }
+            }
+        }
+        output
+    }
+}
+
+// The standings followed by a table for every day anyone has a star for, with how long after the
+// puzzle unlocked each member earned their stars and how long part 2 took after part 1.
+pub fn report(leaderboard: &Leaderboard, format: LeaderboardFormat) -> Result<String, io::Error> {
+    let year: i64 = leaderboard.event.parse().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not a year", leaderboard.event),
+        )
+    })?;
+    let points = points(leaderboard);
+    let scores = local_scores(leaderboard);
+    let last_day = (1..=25)
+        .rev()
+        .find(|&day| {
+            leaderboard
+                .members
+                .values()
+                .any(|member| member.star(day, 1).is_some())
+        })
+        .unwrap_or(0);
+
+    let mut members: Vec<&Member> = leaderboard.members.values().collect();
+    members.sort_by_key(|member| {
+        (
+            Reverse(scores[&member.id]),
+            Reverse(member.stars),
+            member.display_name(),
+        )
+    });
+    let mut standings = Table {
+        title: format!("Standings {}", leaderboard.event),
+        headers: vec!["Rank", "Name", "Days", "Stars", "Score"],
+        text_columns: 3,
+        rows: Vec::new(),
+    };
+    for (place, member) in members.iter().enumerate() {
+        let score = scores[&member.id];
+        if score != member.local_score {
+            warn!(
+                "{} has a local score of {} on the site but {} when recomputed",
+                member.display_name(),
+                member.local_score,
+                score
+            );
+        }
+        let days: String = (1..=last_day)
+            .map(|day| match (member.star(day, 1), member.star(day, 2)) {
+                (Some(_), Some(_)) => '*',
+                (Some(_), None) => '+',
+                _ => '.',
+            })
+            .collect();
+        standings.rows.push(vec![
+            format!("{}.", place + 1),
+            member.display_name(),
+            days,
+            member.stars.to_string(),
+            score.to_string(),
+        ]);
+    }
+
+    let mut tables = vec![standings];
+    for day in 1..=last_day {
+        let unlock = unlock_time(year, day);
+        let mut rows: Vec<(u64, u64, Vec<String>)> = members
+            .iter()
+            .filter_map(|member| {
+                let part_1 = member.star(day, 1)?;
+                let part_2 = member.star(day, 2);
+                let time = |star: &Star| format_duration(star.get_star_ts.saturating_sub(unlock));
+                let row = vec![
+                    member.display_name(),
+                    time(part_1),
+                    part_2.map_or(String::new(), time),
+                    part_2.map_or(String::new(), |star| {
+                        format_duration(star.get_star_ts.saturating_sub(part_1.get_star_ts))
+                    }),
+                    points[&(member.id, day)].to_string(),
+                ];
+                Some((
+                    part_2.map_or(u64::MAX, |star| star.get_star_ts),
+                    part_1.get_star_ts,
+                    row,
+                ))
+            })
+            .collect();
+        rows.sort_by_key(|(part_2, part_1, _)| (*part_2, *part_1));
+        tables.push(Table {
+            title: format!("Day {}", day),
+            headers: vec!["Name", "Part 1", "Part 2", "Delta", "Points"],
+            text_columns: 1,
+            rows: rows.into_iter().map(|(_, _, row)| row).collect(),
+        });
+    }
+
+    Ok(tables
+        .iter()
+        .map(|table| table.render(format))
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+// Downloads the leaderboard, or reuses the copy kept next to the config if it is recent enough.
+// The copy holds the members' names, so like all `.aoc-*` state it is git-ignored.
+pub fn download(site: &Site, id: u64) -> Result<String, io::Error> {
+    let cached = site.state_path(&format!("leaderboard-{}.json", id));
+    let age = fs::metadata(&cached)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+    if age.is_some_and(|age| age < REFRESH_INTERVAL) {
+        info!(
+            "Using the copy of leaderboard {} from {}",
+            id,
+            cached.display()
+        );
+        return read_to_string(&cached);
+    }
+    let contents = site.get(&format!("/{}/leaderboard/private/view/{}.json", YEAR, id))?;
+    parse(&contents)?;
+    fs::write(&cached, &contents)?;
+    Ok(contents)
+}
+
+pub fn handle(
+    file: Option<PathBuf>,
+    id: Option<u64>,
+    format: LeaderboardFormat,
+    site: SiteArgs,
+) -> Result<(), io::Error> {
+    let contents = match (file, id) {
+        (Some(file), _) => read_to_string(file)?,
+        (None, Some(id)) => download(&Site::from_args(&site)?, id)?,
+        (None, None) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Either a leaderboard --file or an --id to download is needed",
+            ))
+        }
+    };
+    print!("{}", report(&parse(&contents)?, format)?);
+    Ok(())
+}
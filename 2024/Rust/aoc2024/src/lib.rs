@@ -0,0 +1,13 @@
+pub mod answers;
+pub mod args;
+pub mod bench;
+pub mod commands;
+pub mod fetch;
+pub mod generate;
+pub mod input;
+pub mod leaderboard;
+pub mod logging;
+pub mod output;
+pub mod parsing;
+pub mod site;
+pub mod submit;
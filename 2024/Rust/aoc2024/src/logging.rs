@@ -0,0 +1,27 @@
+use std::env;
+use std::io::{self, IsTerminal};
+use tracing::Level;
+
+// Logs go to stderr so they never mix with the answers on stdout. Warnings are shown by default,
+// `-q` leaves only errors and each `-v` adds a level: info, then debug, then trace. Events are
+// prefixed with the day and phase spans they happen in, such as `day{day=6}:part_2:`, and
+// coloured when writing to a terminal unless NO_COLOR is set.
+pub fn init(verbose: u8, quiet: bool) {
+    let level = if quiet {
+        Level::ERROR
+    } else {
+        match verbose {
+            0 => Level::WARN,
+            1 => Level::INFO,
+            2 => Level::DEBUG,
+            _ => Level::TRACE,
+        }
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none())
+        .with_target(false)
+        .without_time()
+        .init();
+}
@@ -1,20 +1,148 @@
-mod args;
-mod commands;
-
-use args::Cli;
+use aoc2024::args::{self, Cli, OutputFormat};
+use aoc2024::{
+    answers, bench, commands, fetch, generate, input, leaderboard, logging, output, submit,
+};
 use clap::Parser;
 use std::io;
 
 fn main() -> Result<(), io::Error> {
     let args = Cli::parse();
+    logging::init(args.verbose, args.quiet);
+
+    if args.format == OutputFormat::Json && args.command.day().is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--format json is only supported by the day commands",
+        ));
+    }
+    if args.format == OutputFormat::Json && args.command.has_day_options() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--format json can't be combined with day specific options",
+        ));
+    }
+    if args.time && args.command.has_day_options() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--time can't be combined with day specific options",
+        ));
+    }
+
+    if let args::Commands::Generate {
+        day,
+        seed,
+        size,
+        output,
+        answers,
+    } = args.command
+    {
+        return generate::handle(day, seed, size, output, answers);
+    }
+    if let args::Commands::Verify { answers } = args.command {
+        return answers::verify(answers);
+    }
+    if let args::Commands::Fetch { day, site } = args.command {
+        return fetch::handle(day, site);
+    }
+    if let args::Commands::Submit { day, answers, site } = args.command {
+        return submit::handle(args.input_file, args.part_number, day, answers, site);
+    }
+    if let args::Commands::Leaderboard {
+        file,
+        id,
+        format,
+        site,
+    } = args.command
+    {
+        return leaderboard::handle(file, id, format, site);
+    }
+    let input_file = args.input_file.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "An --input-file is needed for this command",
+        )
+    })?;
+
+    if args.format == OutputFormat::Json {
+        if let Some(day) = args.command.day() {
+            return output::handle(day, args.part_number, &input_file);
+        }
+    }
+
+    if args.time {
+        if let Some(day) = args.command.day() {
+            let solution =
+                commands::solve_reader(day, input::open(&input_file)?, args.part_number)?;
+            println!("{}", solution.answer);
+            if solution.parse_time.is_zero() {
+                println!("parse: streamed, included in solve");
+            } else {
+                println!("parse: {:.3?}", solution.parse_time);
+            }
+            println!("solve: {:.3?}", solution.solve_time);
+            return Ok(());
+        }
+    }
 
+    let _span = args
+        .command
+        .day()
+        .map(|day| tracing::info_span!("day", day).entered());
     match args.command {
-        args::Commands::Day1 => commands::day1::handle(args.input_file, args.part_number),
-        args::Commands::Day2 => commands::day2::handle(args.input_file, args.part_number),
-        args::Commands::Day3 => commands::day3::handle(args.input_file, args.part_number),
-        args::Commands::Day4 => commands::day4::handle(args.input_file, args.part_number),
-        args::Commands::Day5 => commands::day5::handle(args.input_file, args.part_number),
-        args::Commands::Day6 => commands::day6::handle(args.input_file, args.part_number),
-        args::Commands::Day7 => commands::day7::handle(args.input_file, args.part_number),
+        args::Commands::Day1 { lenient, mode } => {
+            commands::day1::handle(input_file, args.part_number, lenient, mode)
+        }
+        args::Commands::Day2 {
+            explain,
+            min_step,
+            max_step,
+            tolerance,
+        } => commands::day2::handle(
+            input_file,
+            args.part_number,
+            explain,
+            min_step,
+            max_step,
+            tolerance,
+        ),
+        args::Commands::Day3 { trace } => {
+            commands::day3::handle(input_file, args.part_number, trace)
+        }
+        args::Commands::Day4 {
+            words,
+            templates,
+            show,
+            list,
+        } => commands::day4::handle(input_file, args.part_number, words, templates, show, list),
+        args::Commands::Day5 { explain, mode } => {
+            commands::day5::handle(input_file, args.part_number, explain, mode)
+        }
+        args::Commands::Day6 => commands::day6::handle(input_file, args.part_number),
+        args::Commands::Day7 => commands::day7::handle(input_file, args.part_number),
+        args::Commands::Bench {
+            day,
+            runs,
+            save,
+            baseline,
+            threshold,
+        } => bench::handle(
+            input_file,
+            args.part_number,
+            day,
+            runs,
+            save,
+            baseline,
+            threshold,
+        ),
+        args::Commands::Record { day, answers } => {
+            answers::record(input_file, args.part_number, day, answers)
+        }
+        args::Commands::Generate { .. }
+        | args::Commands::Verify { .. }
+        | args::Commands::Fetch { .. }
+        | args::Commands::Submit { .. }
+        | args::Commands::Leaderboard { .. } => {
+            unreachable!("these commands are handled before the input is needed")
+        }
     }
 }
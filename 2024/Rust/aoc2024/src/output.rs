@@ -0,0 +1,94 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use crate::commands;
+use crate::input::open;
+
+// One line of `--format json` output. Once there is an input file to read, whatever fails the
+// record is still written so scripts always get an object for the day and part they asked for.
+#[derive(Serialize)]
+pub struct Record {
+    pub day: u8,
+    pub part: u8,
+    pub input_file: PathBuf,
+    // The SHA-256 of the input as it was read, the same as `sha256sum` gives for the file.
+    pub input_sha256: Option<String>,
+    pub answer: Option<String>,
+    pub parse_nanoseconds: Option<u128>,
+    pub solve_nanoseconds: Option<u128>,
+    pub error: Option<String>,
+}
+
+// Hashes the bytes as they are read, so a streamed input never has to be held in memory.
+struct Hashing<R> {
+    reader: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> Read for Hashing<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let count = self.reader.read(buffer)?;
+        self.hasher.update(&buffer[..count]);
+        Ok(count)
+    }
+}
+
+// The input file may be `-` for stdin.
+pub fn record(day: u8, part: u8, input_file: &Path) -> Record {
+    let mut record = Record {
+        day,
+        part,
+        input_file: input_file.to_path_buf(),
+        input_sha256: None,
+        answer: None,
+        parse_nanoseconds: None,
+        solve_nanoseconds: None,
+        error: None,
+    };
+
+    let mut input = match open(input_file) {
+        Ok(reader) => Hashing {
+            reader,
+            hasher: Sha256::new(),
+        },
+        Err(error) => {
+            record.error = Some(error.to_string());
+            return record;
+        }
+    };
+    let result = commands::solve_reader(day, &mut input, part);
+    // Without reading everything the hash would be of part of the input, so it is left out.
+    let is_complete = result.is_ok() || input.read(&mut [0]).is_ok_and(|count| count == 0);
+    if is_complete {
+        record.input_sha256 = Some(
+            input
+                .hasher
+                .finalize()
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+        );
+    }
+    match result {
+        Ok(solution) => {
+            record.answer = Some(solution.answer);
+            record.parse_nanoseconds = Some(solution.parse_time.as_nanos());
+            record.solve_nanoseconds = Some(solution.solve_time.as_nanos());
+        }
+        Err(error) => record.error = Some(error.to_string()),
+    }
+    record
+}
+
+// Writes the record as a single line of JSON, failing afterwards if the day did so the exit
+// status still reflects it.
+pub fn handle(day: u8, part: u8, input_file: &Path) -> Result<(), io::Error> {
+    let record = record(day, part, input_file);
+    println!("{}", serde_json::to_string(&record)?);
+    match record.error {
+        Some(error) => Err(io::Error::other(error)),
+        None => Ok(()),
+    }
+}
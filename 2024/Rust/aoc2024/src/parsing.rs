@@ -0,0 +1,104 @@
+// Building blocks for the shapes puzzle inputs come in. Every parser adds context to its errors,
+// and `parse_all` turns a failure into an error pointing at the line and column that went wrong.
+use nom::{
+    bytes::complete::take_till1,
+    character::complete::{char, digit1, newline, one_of, space0, space1},
+    combinator::{all_consuming, map_res, opt, recognize},
+    error::{context, convert_error, VerboseError},
+    multi::separated_list1,
+    sequence::{pair, separated_pair, terminated},
+    IResult,
+};
+use std::io;
+use std::str::FromStr;
+
+pub type ParseResult<'a, T> = IResult<&'a str, T, VerboseError<&'a str>>;
+
+// Parses the whole of `input`, anything left over is an error rather than being ignored.
+pub fn parse_all<'a, T>(
+    input: &'a str,
+    description: &'static str,
+    parser: impl FnMut(&'a str) -> ParseResult<'a, T>,
+) -> Result<T, io::Error> {
+    match all_consuming(context(description, parser))(input) {
+        Ok((_, value)) => Ok(value),
+        Err(nom::Err::Error(error)) | Err(nom::Err::Failure(error)) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "The input is not {}:\n{}",
+                description,
+                convert_error(input, error)
+            ),
+        )),
+        Err(nom::Err::Incomplete(_)) => Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("The input is not {}, it ends too early", description),
+        )),
+    }
+}
+
+// Any unsigned integer type, an error if the digits don't fit.
+pub fn unsigned<T: FromStr>(input: &str) -> ParseResult<'_, T> {
+    context("an unsigned integer", map_res(digit1, str::parse))(input)
+}
+
+// Any signed integer type, with an optional `+` or `-` sign.
+pub fn signed<T: FromStr>(input: &str) -> ParseResult<'_, T> {
+    context(
+        "an integer",
+        map_res(recognize(pair(opt(one_of("+-")), digit1)), str::parse),
+    )(input)
+}
+
+pub fn whitespace_separated<'a, T>(
+    item: impl FnMut(&'a str) -> ParseResult<'a, T>,
+) -> impl FnMut(&'a str) -> ParseResult<'a, Vec<T>> {
+    context("a whitespace separated list", separated_list1(space1, item))
+}
+
+pub fn comma_separated<'a, T>(
+    item: impl FnMut(&'a str) -> ParseResult<'a, T>,
+) -> impl FnMut(&'a str) -> ParseResult<'a, Vec<T>> {
+    context("a comma separated list", separated_list1(char(','), item))
+}
+
+// One record per line, the last line may or may not end with a newline.
+pub fn lines<'a, T>(
+    record: impl FnMut(&'a str) -> ParseResult<'a, T>,
+) -> impl FnMut(&'a str) -> ParseResult<'a, Vec<T>> {
+    context(
+        "one record per line",
+        terminated(separated_list1(newline, record), opt(newline)),
+    )
+}
+
+// Two sections of lines separated by a blank line.
+pub fn sections<'a, A, B>(
+    first: impl FnMut(&'a str) -> ParseResult<'a, Vec<A>>,
+    second: impl FnMut(&'a str) -> ParseResult<'a, Vec<B>>,
+) -> impl FnMut(&'a str) -> ParseResult<'a, (Vec<A>, Vec<B>)> {
+    context(
+        "two sections separated by a blank line",
+        separated_pair(first, newline, second),
+    )
+}
+
+// A line like `key: values`.
+pub fn key_values<'a, K, V>(
+    key: impl FnMut(&'a str) -> ParseResult<'a, K>,
+    values: impl FnMut(&'a str) -> ParseResult<'a, V>,
+) -> impl FnMut(&'a str) -> ParseResult<'a, (K, V)> {
+    context(
+        "a `key: values` line",
+        separated_pair(key, pair(char(':'), space0), values),
+    )
+}
+
+// The rows of a grid of characters, every row has at least one character but they don't have to
+// be the same length.
+pub fn grid(input: &str) -> ParseResult<'_, Vec<&str>> {
+    context(
+        "a grid of characters",
+        lines(take_till1(|character| character == '\n')),
+    )(input)
+}
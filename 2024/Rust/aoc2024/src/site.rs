@@ -0,0 +1,196 @@
+use serde::Deserialize;
+use std::env;
+use std::fs::{self, read_to_string};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info};
+
+use crate::answers::YEAR;
+use crate::args::SiteArgs;
+
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+
+// The inputs of this repository, found from the crate's directory so the default cache is the
+// same from any working directory.
+const PROBLEMS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../problems");
+
+pub fn day_path(day: u8) -> String {
+    format!("/{}/day/{}", YEAR, day)
+}
+
+// Everything is optional. Relative paths are relative to the directory of the config file, which
+// should not be committed since it holds the session token.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    // The value of the `session` cookie from a logged in browser, AOC_SESSION takes precedence.
+    pub session: Option<String>,
+    // AOC_BASE_URL and --base-url take precedence.
+    pub base_url: Option<String>,
+    // An email address or URL added to the User-Agent so the site's operators can get in touch.
+    pub contact: Option<String>,
+    // Where inputs are cached, defaults to the `problems` directory of this repository whatever
+    // the config's directory.
+    pub problems: Option<PathBuf>,
+    // The least time between two requests in seconds, defaults to 5.
+    pub request_interval: Option<f64>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Config, io::Error> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        toml::from_str(&read_to_string(path)?).map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not a valid config: {}", path.display(), error),
+            )
+        })
+    }
+}
+
+// A client for the Advent of Code site that identifies itself and never makes requests closer
+// together than the configured interval, even across separate runs.
+pub struct Site {
+    pub base_url: String,
+    pub problems: PathBuf,
+    session: Option<String>,
+    user_agent: String,
+    request_interval: Duration,
+    directory: PathBuf,
+    agent: ureq::Agent,
+}
+
+impl Site {
+    pub fn from_args(args: &SiteArgs) -> Result<Site, io::Error> {
+        let mut config = Config::load(&args.config)?;
+        if let Ok(session) = env::var("AOC_SESSION") {
+            config.session = Some(session);
+        }
+        if let Ok(base_url) = env::var("AOC_BASE_URL") {
+            config.base_url = Some(base_url);
+        }
+        if let Some(base_url) = &args.base_url {
+            config.base_url = Some(base_url.clone());
+        }
+        Site::new(config, args.config.parent().unwrap_or(Path::new("")))
+    }
+
+    // State such as the time of the last request is kept in `directory`, next to the config.
+    pub fn new(config: Config, directory: &Path) -> Result<Site, io::Error> {
+        let request_interval = config.request_interval.unwrap_or(5.0);
+        let request_interval = Duration::try_from_secs_f64(request_interval).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a valid request interval", request_interval),
+            )
+        })?;
+        let user_agent = match &config.contact {
+            Some(contact) => format!("aoc2024/{} ({})", env!("CARGO_PKG_VERSION"), contact),
+            None => format!("aoc2024/{}", env!("CARGO_PKG_VERSION")),
+        };
+        Ok(Site {
+            base_url: config
+                .base_url
+                .unwrap_or_else(|| DEFAULT_BASE_URL.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            problems: match config.problems {
+                Some(problems) => directory.join(problems),
+                None => PathBuf::from(PROBLEMS),
+            },
+            session: config.session.map(|session| session.trim().to_string()),
+            agent: ureq::AgentBuilder::new()
+                .user_agent(&user_agent)
+                .timeout(Duration::from_secs(30))
+                .build(),
+            user_agent,
+            request_interval,
+            directory: directory.to_path_buf(),
+        })
+    }
+
+    fn session(&self) -> Result<&str, io::Error> {
+        self.session.as_deref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "No session token, set AOC_SESSION or `session` in the config",
+            )
+        })
+    }
+
+    // A file next to the config for state that has to last between runs, such as `.aoc-{name}`.
+    pub fn state_path(&self, name: &str) -> PathBuf {
+        self.directory.join(format!(".aoc-{}", name))
+    }
+
+    pub fn read_time(&self, name: &str) -> Option<SystemTime> {
+        read_to_string(self.state_path(name))
+            .ok()
+            .and_then(|contents| contents.trim().parse().ok())
+            .map(|milliseconds| UNIX_EPOCH + Duration::from_millis(milliseconds))
+    }
+
+    // Rounded up to the millisecond, so a wait worked out from the stored time is never too short.
+    pub fn write_time(&self, name: &str, time: SystemTime) -> Result<(), io::Error> {
+        let milliseconds = time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros()
+            .div_ceil(1000);
+        fs::write(self.state_path(name), milliseconds.to_string())
+    }
+
+    fn wait_for_turn(&self) -> Result<(), io::Error> {
+        if let Some(wait) = self.read_time("last-request").and_then(|last| {
+            (last + self.request_interval)
+                .duration_since(SystemTime::now())
+                .ok()
+        }) {
+            info!("Waiting {:.1?} before the next request", wait);
+            sleep(wait);
+        }
+        self.write_time("last-request", SystemTime::now())
+    }
+
+    fn request(&self, method: &str, path: &str) -> Result<ureq::Request, io::Error> {
+        let session = self.session()?;
+        self.wait_for_turn()?;
+        let url = format!("{}{}", self.base_url, path);
+        debug!(user_agent = self.user_agent, "{} {}", method, url);
+        Ok(self
+            .agent
+            .request(method, &url)
+            .set("Cookie", &format!("session={}", session)))
+    }
+
+    pub fn get(&self, path: &str) -> Result<String, io::Error> {
+        response(self.request("GET", path)?.call())
+    }
+
+    pub fn post_form(&self, path: &str, form: &[(&str, &str)]) -> Result<String, io::Error> {
+        response(self.request("POST", path)?.send_form(form))
+    }
+}
+
+fn response(result: Result<ureq::Response, ureq::Error>) -> Result<String, io::Error> {
+    match result {
+        Ok(response) => response.into_string(),
+        Err(ureq::Error::Status(status, response)) => {
+            let kind = match status {
+                400 | 401 | 403 => io::ErrorKind::PermissionDenied,
+                404 => io::ErrorKind::NotFound,
+                _ => io::ErrorKind::Other,
+            };
+            let body = response.into_string().unwrap_or_default();
+            Err(io::Error::new(
+                kind,
+                format!("The site answered {}: {}", status, body.trim()),
+            ))
+        }
+        Err(error) => Err(io::Error::other(error)),
+    }
+}
@@ -0,0 +1,208 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::info;
+
+use crate::answers::{AnswerStore, Reason};
+use crate::args::SiteArgs;
+use crate::commands;
+use crate::fetch::fetch;
+use crate::input::read_input;
+use crate::site::{day_path, Site};
+
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    Correct,
+    Rejected(Reason),
+    // The site wants this long between submissions.
+    RateLimited(Duration),
+    // The part has been solved already, so the site didn't check the answer.
+    AlreadySolved,
+    Unknown,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Response {
+    pub verdict: Verdict,
+    // How long to wait before the next submission, after a wrong answer or when rate limited.
+    pub wait: Option<Duration>,
+    // The text of the site's reply without any markup.
+    pub message: String,
+}
+
+// The reply is a page with the message in its `<article>`, only its text matters.
+pub fn parse_response(html: &str) -> Response {
+    let article = match (html.find("<article"), html.find("</article>")) {
+        (Some(start), Some(end)) if start < end => &html[start..end],
+        _ => html,
+    };
+    let message = strip_tags(article);
+
+    // Wrong answers come with "Please wait one minute" or, after a few, "please wait 5 minutes".
+    let wrong_wait = after(&message.to_lowercase(), "please wait ")
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|count| match count {
+            "one" | "a" => Some(1),
+            count => count.parse().ok(),
+        })
+        .map(|minutes| Duration::from_secs(60 * minutes));
+    let (verdict, wait) = if message.contains("That's the right answer") {
+        (Verdict::Correct, None)
+    } else if message.contains("That's not the right answer") {
+        let reason = if message.contains("too high") {
+            Reason::TooHigh
+        } else if message.contains("too low") {
+            Reason::TooLow
+        } else {
+            Reason::Wrong
+        };
+        (Verdict::Rejected(reason), wrong_wait)
+    } else if message.contains("You gave an answer too recently") {
+        let wait = after(&message, "You have ")
+            .and_then(|rest| rest.split(" left to wait").next())
+            .and_then(parse_duration)
+            .unwrap_or(Duration::from_secs(60));
+        (Verdict::RateLimited(wait), Some(wait))
+    } else if message.contains("You don't seem to be solving the right level") {
+        (Verdict::AlreadySolved, None)
+    } else {
+        (Verdict::Unknown, None)
+    };
+    Response {
+        verdict,
+        wait,
+        message,
+    }
+}
+
+fn after<'a>(text: &'a str, marker: &str) -> Option<&'a str> {
+    text.find(marker).map(|start| &text[start + marker.len()..])
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for character in html.chars() {
+        match character {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(character),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+// Durations like `42s` or `1m 5s`.
+fn parse_duration(text: &str) -> Option<Duration> {
+    let mut seconds = 0;
+    for part in text.split_whitespace() {
+        let unit = match part.chars().last()? {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        seconds += unit * part[..part.len() - 1].parse::<u64>().ok()?;
+    }
+    Some(Duration::from_secs(seconds))
+}
+
+// Solves the day, checks the answer against what is already known and only then submits it.
+// Anything but a correct answer is an error, so scripts can rely on the exit status.
+pub fn submit(
+    site: &Site,
+    store: &mut AnswerStore,
+    input_file: &Path,
+    day: u8,
+    part_number: u8,
+) -> Result<Response, io::Error> {
+    let contents = read_input(input_file)?;
+    let answer = commands::solve(day, &contents, part_number)?.answer;
+    let name = format!("Day {} part {}", day, part_number);
+
+    if let Some(entry) = store.get(day, part_number, input_file)? {
+        if entry.answer == answer {
+            return Ok(Response {
+                verdict: Verdict::Correct,
+                wait: None,
+                message: format!("{} was already accepted as {}", name, answer),
+            });
+        }
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} was accepted as {} but the answer is now {}",
+                name, entry.answer, answer
+            ),
+        ));
+    }
+    if let Some(reason) = store.known_wrong(day, part_number, input_file, &answer)? {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Not submitting, {}", reason),
+        ));
+    }
+    if let Some(wait) = site
+        .read_time("submit-after")
+        .and_then(|after| after.duration_since(SystemTime::now()).ok())
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::WouldBlock,
+            format!(
+                "The site asked to wait before submitting again, {}s left",
+                wait.as_secs() + 1
+            ),
+        ));
+    }
+
+    info!("Submitting {} for {}", answer, name);
+    let response = parse_response(&site.post_form(
+        &format!("{}/answer", day_path(day)),
+        &[("level", &part_number.to_string()), ("answer", &answer)],
+    )?);
+    if let Some(wait) = response.wait {
+        site.write_time("submit-after", SystemTime::now() + wait)?;
+    }
+    match &response.verdict {
+        Verdict::Correct => {
+            store.insert(day, part_number, input_file, answer)?;
+            store.save()?;
+        }
+        Verdict::Rejected(reason) => {
+            store.reject(day, part_number, input_file, answer, *reason)?;
+            store.save()?;
+        }
+        Verdict::RateLimited(_) | Verdict::AlreadySolved | Verdict::Unknown => {}
+    }
+    Ok(response)
+}
+
+pub fn handle(
+    input_file: Option<PathBuf>,
+    part_number: u8,
+    day: u8,
+    answers: PathBuf,
+    site: SiteArgs,
+) -> Result<(), io::Error> {
+    let site = Site::from_args(&site)?;
+    let input_file = match input_file {
+        Some(input_file) => input_file,
+        None => fetch(&site, day)?,
+    };
+    let mut store = AnswerStore::load(&answers)?;
+    let response = submit(&site, &mut store, &input_file, day, part_number)?;
+    println!("{}", response.message);
+    match response.verdict {
+        Verdict::Correct => Ok(()),
+        Verdict::Rejected(reason) => Err(io::Error::other(format!("The answer is {}", reason))),
+        Verdict::RateLimited(wait) => Err(io::Error::new(
+            io::ErrorKind::WouldBlock,
+            format!("Submitting too often, wait {}s", wait.as_secs()),
+        )),
+        Verdict::AlreadySolved => Err(io::Error::other(
+            "The part is already solved, `record` its answer to keep it",
+        )),
+        Verdict::Unknown => Err(io::Error::other("The site's reply was not recognised")),
+    }
+}
@@ -0,0 +1,42 @@
+use aoc2024::answers::{check, AnswerStore, Outcome};
+use aoc2024::generate::generate;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn stores_answers_relative_to_the_file_and_finds_mismatches() {
+    let directory = env::temp_dir().join(format!("aoc2024-answers-{}", std::process::id()));
+    fs::create_dir_all(directory.join("problems")).unwrap();
+    let input_file = directory.join("problems").join("day1.txt");
+    let generated = generate(1, 3, Some(40)).unwrap();
+    fs::write(&input_file, &generated.input).unwrap();
+    let answers = directory.join("answers.toml");
+
+    let mut store = AnswerStore::load(&answers).unwrap();
+    assert!(store.entries().is_empty());
+    let right = generated.answers[0].clone().unwrap();
+    assert_eq!(
+        store.insert(1, 1, &input_file, "0".to_string()).unwrap(),
+        None
+    );
+    assert_eq!(
+        store.insert(1, 1, &input_file, right.clone()).unwrap(),
+        Some("0".to_string())
+    );
+    store.insert(1, 2, &input_file, "0".to_string()).unwrap();
+    store.save().unwrap();
+
+    let store = AnswerStore::load(&answers).unwrap();
+    let part_1 = store.get(1, 1, &input_file).unwrap().unwrap();
+    assert_eq!(part_1.answer, right);
+    assert_eq!(part_1.input_file, Path::new("problems").join("day1.txt"));
+    assert!(matches!(check(&store, part_1), Outcome::Match));
+    let part_2 = store.get(1, 2, &input_file).unwrap().unwrap();
+    match check(&store, part_2) {
+        Outcome::Mismatch(answer) => assert_eq!(Some(answer), generated.answers[1]),
+        _ => panic!("day 1 part 2 should not match"),
+    }
+
+    fs::remove_dir_all(&directory).unwrap();
+}
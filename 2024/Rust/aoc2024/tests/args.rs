@@ -0,0 +1,32 @@
+use aoc2024::args::Cli;
+use clap::Parser;
+
+fn has_day_options(arguments: &[&str]) -> bool {
+    Cli::try_parse_from([&["aoc2024", "-i", "input.txt"], arguments].concat())
+        .unwrap()
+        .command
+        .has_day_options()
+}
+
+#[test]
+fn default_day_options_are_not_options() {
+    assert!(!has_day_options(&["day1"]));
+    assert!(!has_day_options(&[
+        "day2",
+        "--min-step",
+        "1",
+        "--max-step",
+        "3"
+    ]));
+    assert!(!has_day_options(&["day6"]));
+    assert!(!has_day_options(&["record", "3"]));
+}
+
+#[test]
+fn changed_day_options_are_options() {
+    assert!(has_day_options(&["day1", "--lenient"]));
+    assert!(has_day_options(&["day2", "--max-step", "4"]));
+    assert!(has_day_options(&["day3", "--trace"]));
+    assert!(has_day_options(&["day4", "-w", "SAMX"]));
+    assert!(has_day_options(&["day5", "--explain"]));
+}
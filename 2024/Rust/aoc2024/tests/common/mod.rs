@@ -0,0 +1,134 @@
+// Shared by several test files, each of which uses only some of it.
+#![allow(dead_code)]
+
+use aoc2024::site::{Config, Site};
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// A request as the stub server saw it, header names are lower case.
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// A local HTTP server standing in for the Advent of Code site. `respond` gets each request and
+// returns the status and body to answer with.
+pub struct Stub {
+    pub base_url: String,
+    pub requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl Stub {
+    pub fn start(respond: impl Fn(&Request) -> (u16, String) + Send + 'static) -> Stub {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&requests);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(&mut stream);
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let mut parts = line.split_whitespace();
+                let method = parts.next().unwrap_or_default().to_string();
+                let path = parts.next().unwrap_or_default().to_string();
+                let mut headers = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((key, value)) = line.split_once(':') {
+                        headers.push((key.trim().to_lowercase(), value.trim().to_string()));
+                    }
+                }
+                let length = headers
+                    .iter()
+                    .find(|(key, _)| key == "content-length")
+                    .map_or(0, |(_, value)| value.parse().unwrap());
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let request = Request {
+                    method,
+                    path,
+                    headers,
+                    body: String::from_utf8(body).unwrap(),
+                };
+
+                let (status, body) = respond(&request);
+                seen.lock().unwrap().push(request);
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Stub\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        Stub { base_url, requests }
+    }
+
+    pub fn request_count(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+
+    // A config for a logged in client of this stub that doesn't wait between requests.
+    pub fn config(&self) -> Config {
+        Config {
+            session: Some("abc".to_string()),
+            base_url: Some(self.base_url.clone()),
+            request_interval: Some(0.0),
+            ..Config::default()
+        }
+    }
+}
+
+// A site keeping its state in a temporary directory of its own, which is removed when dropped.
+pub struct TestSite {
+    pub directory: PathBuf,
+    pub site: Site,
+}
+
+impl TestSite {
+    pub fn new(name: &str, config: Config) -> TestSite {
+        let directory = env::temp_dir().join(format!("aoc2024-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        TestSite {
+            site: Site::new(config, &directory).unwrap(),
+            directory,
+        }
+    }
+
+    // Another client sharing this one's directory.
+    pub fn with_config(&self, config: Config) -> Site {
+        Site::new(config, &self.directory).unwrap()
+    }
+}
+
+impl Drop for TestSite {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.directory);
+    }
+}
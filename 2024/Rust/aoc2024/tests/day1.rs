@@ -0,0 +1,122 @@
+use aoc2024::args::{AnalysisFormat, AnalysisTable};
+use aoc2024::commands::day1;
+use proptest::prelude::*;
+
+// Pairs the smallest remaining IDs of each list one at a time, as described in the puzzle.
+fn oracle_part_1(left_ids: &[u64], right_ids: &[u64]) -> u128 {
+    let mut left_ids = left_ids.to_vec();
+    let mut right_ids = right_ids.to_vec();
+    let mut distance = 0;
+    while !left_ids.is_empty() {
+        let left = left_ids.iter().min().copied().unwrap();
+        let right = right_ids.iter().min().copied().unwrap();
+        left_ids.remove(left_ids.iter().position(|&id| id == left).unwrap());
+        right_ids.remove(right_ids.iter().position(|&id| id == right).unwrap());
+        distance += left.abs_diff(right) as u128;
+    }
+    distance
+}
+
+fn oracle_part_2(left_ids: &[u64], right_ids: &[u64]) -> u128 {
+    left_ids
+        .iter()
+        .map(|&left| {
+            left as u128 * right_ids.iter().filter(|&&right| right == left).count() as u128
+        })
+        .sum()
+}
+
+fn input(pairs: &[(u64, u64)]) -> String {
+    pairs
+        .iter()
+        .map(|(left, right)| format!("{}   {}\n", left, right))
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn part_1_matches_oracle(pairs in prop::collection::vec((0u64..20, 0u64..20), 1..30)) {
+        let (left_ids, right_ids): (Vec<u64>, Vec<u64>) = pairs.iter().cloned().unzip();
+        let id_lists = day1::parse(&input(&pairs)).unwrap();
+        prop_assert_eq!(day1::part_1(&id_lists).unwrap(), oracle_part_1(&left_ids, &right_ids));
+    }
+
+    #[test]
+    fn part_2_matches_oracle(pairs in prop::collection::vec((0u64..20, 0u64..20), 1..30)) {
+        let (left_ids, right_ids): (Vec<u64>, Vec<u64>) = pairs.iter().cloned().unzip();
+        let id_lists = day1::parse(&input(&pairs)).unwrap();
+        prop_assert_eq!(day1::part_2(&id_lists).unwrap(), oracle_part_2(&left_ids, &right_ids));
+    }
+
+    #[test]
+    fn wide_ids_do_not_overflow(pairs in prop::collection::vec((any::<u64>(), any::<u64>()), 1..10)) {
+        let (left_ids, right_ids): (Vec<u64>, Vec<u64>) = pairs.iter().cloned().unzip();
+        let id_lists = day1::parse(&input(&pairs)).unwrap();
+        prop_assert_eq!(day1::part_1(&id_lists).unwrap(), oracle_part_1(&left_ids, &right_ids));
+        prop_assert_eq!(day1::part_2(&id_lists).unwrap(), oracle_part_2(&left_ids, &right_ids));
+    }
+}
+
+// Every CSV table is a single header and rows with as many fields, with nothing else around it.
+fn parse_csv(csv: &str) -> (Vec<&str>, Vec<Vec<&str>>) {
+    let mut lines = csv.lines();
+    let headers: Vec<&str> = lines.next().unwrap().split(',').collect();
+    let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+    for row in &rows {
+        assert_eq!(row.len(), headers.len(), "{:?} in\n{}", row, csv);
+    }
+    (headers, rows)
+}
+
+#[test]
+fn analysis_csv_is_one_table() {
+    let analyze = |table| {
+        let mut left_ids = vec![3, 4, 2, 1, 3, 3];
+        let mut right_ids = vec![4, 3, 5, 3, 9, 3];
+        day1::analyze(&mut left_ids, &mut right_ids, AnalysisFormat::Csv, 2, table)
+    };
+
+    let pairs = analyze(Some(AnalysisTable::Pairs)).unwrap();
+    let (headers, rows) = parse_csv(&pairs);
+    assert_eq!(headers, ["index", "left", "right", "distance"]);
+    assert_eq!(rows.len(), 6);
+    let total: u64 = rows.iter().map(|row| row[3].parse::<u64>().unwrap()).sum();
+    assert_eq!(total, 11);
+
+    let overlap = analyze(Some(AnalysisTable::Overlap)).unwrap();
+    let (headers, rows) = parse_csv(&overlap);
+    assert_eq!(headers, ["id", "left count", "right count", "similarity"]);
+    assert_eq!(rows, [["3", "3", "3", "27"], ["4", "1", "1", "4"]]);
+
+    for table in [
+        AnalysisTable::Summary,
+        AnalysisTable::Statistics,
+        AnalysisTable::Distances,
+    ] {
+        parse_csv(&analyze(Some(table)).unwrap());
+    }
+    assert!(analyze(None).is_err());
+}
+
+#[test]
+fn lenient_parsing_skips_bad_lines() {
+    let contents = "3   4\n4 x\n2   5\n";
+    let id_lists = day1::parse_id_lists(contents, true).unwrap();
+    assert_eq!(id_lists.left_ids, [3, 2]);
+    assert_eq!(id_lists.right_ids, [4, 5]);
+
+    let error = day1::parse_id_lists(contents, false).err().unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(error.to_string(), "Line 2 is not a pair of IDs: \"4 x\"");
+}
+
+#[test]
+fn analysis_needs_lists_of_the_same_length() {
+    let error =
+        day1::analyze(&mut [3, 4, 2], &mut [4, 3], AnalysisFormat::Table, 2, None).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(
+        error.to_string(),
+        "The lists have different lengths, 3 left IDs and 2 right IDs"
+    );
+}
@@ -0,0 +1,151 @@
+use aoc2024::commands::day2::{self, Direction, Report, Safety, SafetyRules};
+use proptest::prelude::*;
+
+fn is_safe(levels: &[i32]) -> bool {
+    let steps: Vec<i32> = levels.windows(2).map(|pair| pair[1] - pair[0]).collect();
+    steps.iter().all(|step| (1..=3).contains(step))
+        || steps.iter().all(|step| (-3..=-1).contains(step))
+}
+
+// Tries removing every combination of up to `tolerance` levels.
+fn oracle_is_safe(levels: &[i32], tolerance: usize) -> bool {
+    if is_safe(levels) {
+        return true;
+    }
+    tolerance > 0
+        && (0..levels.len()).any(|index| {
+            let mut remaining = levels.to_vec();
+            remaining.remove(index);
+            oracle_is_safe(&remaining, tolerance - 1)
+        })
+}
+
+fn input(reports: &[Vec<i32>]) -> String {
+    reports
+        .iter()
+        .map(|levels| {
+            let levels: Vec<String> = levels.iter().map(|level| level.to_string()).collect();
+            levels.join(" ")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Mostly gently sloping reports, so plenty of them are safe or nearly safe in either direction.
+fn reports() -> impl Strategy<Value = Vec<Vec<i32>>> {
+    let report = (0i32..20, prop::collection::vec(-4i32..=4, 0..8)).prop_map(|(start, steps)| {
+        let mut levels = vec![start];
+        for step in steps {
+            levels.push(levels.last().unwrap() + step);
+        }
+        levels
+    });
+    prop::collection::vec(report, 1..20)
+}
+
+fn count_oracle(reports: &[Vec<i32>], tolerance: usize) -> usize {
+    reports
+        .iter()
+        .filter(|levels| oracle_is_safe(levels, tolerance))
+        .count()
+}
+
+fn safety(levels: &[i32], min_step: i64, tolerance: usize) -> Safety {
+    let report = Report {
+        levels: levels.to_vec(),
+    };
+    report.safety(&SafetyRules::new(min_step, 3, tolerance).unwrap())
+}
+
+fn unsafe_reason(levels: &[i32], min_step: i64) -> String {
+    match safety(levels, min_step, 0) {
+        Safety::Unsafe(violation) => violation.to_string(),
+        safety => panic!("{:?} is {:?}", levels, safety),
+    }
+}
+
+#[test]
+fn names_the_level_to_remove() {
+    assert_eq!(safety(&[1, 2, 3], 1, 1), Safety::Safe);
+    assert_eq!(
+        safety(&[9, 1, 2, 3], 1, 1),
+        Safety::SafeWithoutLevels(vec![0])
+    );
+    assert_eq!(
+        safety(&[1, 2, 9, 3, 4], 1, 1),
+        Safety::SafeWithoutLevels(vec![2])
+    );
+    assert_eq!(
+        safety(&[1, 2, 3, 9], 1, 1),
+        Safety::SafeWithoutLevels(vec![3])
+    );
+    assert_eq!(
+        safety(&[1, 9, 2, 8, 3], 1, 2),
+        Safety::SafeWithoutLevels(vec![1, 3])
+    );
+}
+
+#[test]
+fn names_why_a_report_is_unsafe() {
+    match safety(&[5, 4, 4, 3], 1, 0) {
+        Safety::Unsafe(violation) => {
+            assert_eq!((violation.index, violation.next_index), (1, 2));
+            assert_eq!(violation.direction, Direction::Decreasing);
+        }
+        safety => panic!("{:?}", safety),
+    }
+    assert_eq!(
+        unsafe_reason(&[5, 4, 4, 3], 1),
+        "levels 1 and 2 (4 -> 4) do not change"
+    );
+    assert_eq!(
+        unsafe_reason(&[1, 2, 1], 1),
+        "levels 1 and 2 (2 -> 1) stop increasing"
+    );
+    assert_eq!(
+        unsafe_reason(&[1, 3, 8], 1),
+        "levels 1 and 2 (3 -> 8) change by 5, more than 3"
+    );
+    assert_eq!(
+        unsafe_reason(&[1, 4, 5], 2),
+        "levels 1 and 2 (4 -> 5) change by 1, less than 2"
+    );
+    assert!(matches!(safety(&[1, 9, 2, 8], 1, 1), Safety::Unsafe(_)));
+}
+
+proptest! {
+    #[test]
+    fn part_1_matches_oracle(reports in reports()) {
+        let parsed = day2::parse(&input(&reports)).unwrap();
+        prop_assert_eq!(day2::part_1(&parsed).unwrap(), count_oracle(&reports, 0));
+    }
+
+    #[test]
+    fn part_2_matches_oracle(reports in reports()) {
+        let parsed = day2::parse(&input(&reports)).unwrap();
+        prop_assert_eq!(day2::part_2(&parsed).unwrap(), count_oracle(&reports, 1));
+    }
+
+    #[test]
+    fn reversed_reports_are_equally_safe(reports in reports(), tolerance in 0usize..3) {
+        let reversed: Vec<Vec<i32>> = reports
+            .iter()
+            .map(|levels| levels.iter().rev().cloned().collect())
+            .collect();
+        let rules = SafetyRules::new(1, 3, tolerance).unwrap();
+        prop_assert_eq!(
+            day2::count_safe_reports(&day2::parse(&input(&reports)).unwrap(), &rules),
+            day2::count_safe_reports(&day2::parse(&input(&reversed)).unwrap(), &rules)
+        );
+    }
+
+    #[test]
+    fn any_tolerance_matches_oracle(reports in reports(), tolerance in 0usize..4) {
+        let parsed: Vec<Report> = day2::parse(&input(&reports)).unwrap();
+        let rules = SafetyRules::new(1, 3, tolerance).unwrap();
+        prop_assert_eq!(
+            day2::count_safe_reports(&parsed, &rules),
+            count_oracle(&reports, tolerance)
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use aoc2024::args::TraceFormat;
+use aoc2024::commands::day3;
+use proptest::prelude::*;
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+
+// Returns the value of a `mul(a,b)` starting at the beginning of `memory`, checking each
+// character one at a time.
+fn multiplication_at(memory: &[char]) -> Option<i64> {
+    let mut rest = memory.strip_prefix(&['m', 'u', 'l', '('])?;
+    let mut operands = Vec::new();
+    for separator in [',', ')'] {
+        let digits = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+        if digits == 0 || digits > 3 || rest.get(digits) != Some(&separator) {
+            return None;
+        }
+        let operand: String = rest[..digits].iter().collect();
+        operands.push(operand.parse::<i64>().unwrap());
+        rest = &rest[digits + 1..];
+    }
+    Some(operands[0] * operands[1])
+}
+
+fn oracle(memory: &str, use_conditionals: bool) -> i64 {
+    let memory: Vec<char> = memory.chars().collect();
+    let mut is_enabled = true;
+    let mut sum = 0;
+    for index in 0..memory.len() {
+        let rest: String = memory[index..].iter().collect();
+        if use_conditionals && rest.starts_with("do()") {
+            is_enabled = true;
+        } else if use_conditionals && rest.starts_with("don't()") {
+            is_enabled = false;
+        } else if let Some(product) = multiplication_at(&memory[index..]) {
+            if is_enabled {
+                sum += product;
+            }
+        }
+    }
+    sum
+}
+
+// Corrupted memory built from pieces of instructions, so near misses are as common as matches.
+fn memory() -> impl Strategy<Value = String> {
+    let fragment = prop_oneof![
+        Just("mul(".to_string()),
+        Just("mul".to_string()),
+        Just("do()".to_string()),
+        Just("don't()".to_string()),
+        Just("do".to_string()),
+        Just(",".to_string()),
+        Just(")".to_string()),
+        Just("(".to_string()),
+        Just(" ".to_string()),
+        Just("\n".to_string()),
+        (0u32..10000).prop_map(|number| number.to_string()),
+        "[a-z!@#%]{1,3}",
+        ((0u32..1000), (0u32..1000)).prop_map(|(a, b)| format!("mul({},{})", a, b)),
+    ];
+    prop::collection::vec(fragment, 0..60).prop_map(|fragments| fragments.concat())
+}
+
+// Hands out only a few bytes per read, cycling through `sizes`, so instructions are split across
+// refills of the lexer's buffer in every possible place.
+struct Trickle<'a> {
+    remaining: &'a [u8],
+    sizes: Vec<usize>,
+    reads: usize,
+}
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let size = self.sizes[self.reads % self.sizes.len()]
+            .min(buffer.len())
+            .min(self.remaining.len());
+        self.reads += 1;
+        buffer[..size].copy_from_slice(&self.remaining[..size]);
+        self.remaining = &self.remaining[size..];
+        Ok(size)
+    }
+}
+
+fn trickle(memory: &str, sizes: Vec<usize>) -> Trickle<'_> {
+    Trickle {
+        remaining: memory.as_bytes(),
+        sizes,
+        reads: 0,
+    }
+}
+
+fn offsets<R: Read>(reader: R) -> Vec<(u64, u64, u64)> {
+    day3::Lexer::new(reader, &[day3::MUL, day3::DO, day3::DONT])
+        .map(|token| {
+            let token = token.unwrap();
+            (token.offset, token.line, token.column)
+        })
+        .collect()
+}
+
+const TRACED: &str = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+
+fn traced(format: TraceFormat) -> (String, day3::TraceSummary) {
+    let mut output = Vec::new();
+    let summary = day3::trace(
+        TRACED.as_bytes(),
+        &[day3::MUL, day3::DO, day3::DONT],
+        format,
+        &mut output,
+    )
+    .unwrap();
+    (String::from_utf8(output).unwrap(), summary)
+}
+
+#[test]
+fn traces_every_instruction_and_the_totals() {
+    let (text, summary) = traced(TraceFormat::Text);
+    assert_eq!(
+        summary.instruction_counts,
+        BTreeMap::from([("do", 1), ("don't", 1), ("mul", 4)])
+    );
+    assert_eq!((summary.enabled_count, summary.enabled_sum), (2, 48));
+    assert_eq!((summary.disabled_count, summary.disabled_sum), (2, 113));
+    assert_eq!(text.lines().count(), 6 + 1 + 4);
+    assert!(
+        text.lines().next().unwrap().contains("mul(2,4)"),
+        "{}",
+        text
+    );
+    assert!(text.ends_with(
+        "instructions: do: 1, don't: 1, mul: 4\nenabled: 2 (sum 48)\ndisabled: 2 (sum 113)\ntotal: 161\n"
+    ));
+
+    let (csv, _) = traced(TraceFormat::Csv);
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        rows[0],
+        "offset,line,column,instruction,operands,value,enabled"
+    );
+    assert_eq!(rows[1], "1,1,2,mul,2 4,8,true");
+    assert_eq!(rows[3], "28,1,29,mul,5 5,25,false");
+    assert_eq!(rows.len(), 7);
+
+    let (json, _) = traced(TraceFormat::Json);
+    let lines: Vec<serde_json::Value> = json
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 7);
+    assert_eq!(lines[0]["instruction"], "mul");
+    assert_eq!(lines[0]["value"], 8);
+    assert_eq!(lines[1]["instruction"], "don't");
+    assert_eq!(lines[1]["value"], serde_json::Value::Null);
+    assert_eq!(lines[6]["summary"]["enabled_sum"], 48);
+    assert_eq!(lines[6]["summary"]["instruction_counts"]["mul"], 4);
+}
+
+#[test]
+fn instructions_split_across_reads() {
+    let memory =
+        "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))\nmul(123,4)";
+    for size in 1..=8 {
+        for shift in 0..size {
+            // The first read is shorter so every boundary position is tried.
+            let sizes = vec![shift.max(1), size];
+            let state = day3::run(
+                trickle(memory, sizes.clone()),
+                &[day3::MUL, day3::DO, day3::DONT],
+            )
+            .unwrap();
+            assert_eq!(state.sum, oracle(memory, true), "reads of {:?}", sizes);
+            assert_eq!(offsets(trickle(memory, sizes)), offsets(memory.as_bytes()));
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn part_1_matches_oracle(memory in memory()) {
+        let tokens = day3::parse(&memory).unwrap();
+        prop_assert_eq!(day3::part_1(&tokens).unwrap(), oracle(&memory, false));
+    }
+
+    #[test]
+    fn part_2_matches_oracle(memory in memory()) {
+        let tokens = day3::parse(&memory).unwrap();
+        prop_assert_eq!(day3::part_2(&tokens).unwrap(), oracle(&memory, true));
+    }
+
+    #[test]
+    fn streaming_matches_oracle(memory in memory()) {
+        let state = day3::run(memory.as_bytes(), &[day3::MUL, day3::DO, day3::DONT]).unwrap();
+        prop_assert_eq!(state.sum, oracle(&memory, true));
+    }
+
+    #[test]
+    fn streaming_a_few_bytes_at_a_time_matches_oracle(
+        memory in memory(),
+        sizes in prop::collection::vec(1usize..=3, 1..8),
+    ) {
+        let state = day3::run(trickle(&memory, sizes.clone()), &[day3::MUL, day3::DO, day3::DONT]).unwrap();
+        prop_assert_eq!(state.sum, oracle(&memory, true));
+        prop_assert_eq!(offsets(trickle(&memory, sizes)), offsets(memory.as_bytes()));
+    }
+}
@@ -0,0 +1,172 @@
+use aoc2024::commands::day4::{self, Template};
+use proptest::prelude::*;
+
+const DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+fn letter_at(grid: &[Vec<char>], line: isize, character: isize) -> Option<char> {
+    grid.get(usize::try_from(line).ok()?)?
+        .get(usize::try_from(character).ok()?)
+        .copied()
+}
+
+// Reads four letters from every cell in every direction.
+fn oracle_part_1(grid: &[Vec<char>]) -> usize {
+    let mut count = 0;
+    for line in 0..grid.len() as isize {
+        for character in 0..grid[0].len() as isize {
+            for (line_step, character_step) in DIRECTIONS {
+                let word: Option<String> = (0..4)
+                    .map(|i| letter_at(grid, line + line_step * i, character + character_step * i))
+                    .collect();
+                if word.as_deref() == Some("XMAS") {
+                    count += 1;
+                }
+            }
+        }
+    }
+    count
+}
+
+// Checks both diagonals through every `A` read `MAS` one way or the other.
+fn oracle_part_2(grid: &[Vec<char>]) -> usize {
+    let mut count = 0;
+    for line in 0..grid.len() as isize {
+        for character in 0..grid[0].len() as isize {
+            let diagonal = |line_step: isize| -> Option<String> {
+                (-1..=1)
+                    .map(|i| letter_at(grid, line + line_step * i, character + i))
+                    .collect()
+            };
+            let is_mas = |word: Option<String>| matches!(word.as_deref(), Some("MAS" | "SAM"));
+            if letter_at(grid, line, character) == Some('A')
+                && is_mas(diagonal(1))
+                && is_mas(diagonal(-1))
+            {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+fn grid() -> impl Strategy<Value = Vec<Vec<char>>> {
+    (1usize..10, 1usize..10).prop_flat_map(|(lines, characters)| {
+        prop::collection::vec(
+            prop::collection::vec(prop::sample::select(vec!['X', 'M', 'A', 'S']), characters),
+            lines,
+        )
+    })
+}
+
+fn input(grid: &[Vec<char>]) -> String {
+    grid.iter()
+        .map(|line| line.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+#[test]
+fn palindromes_are_counted_once() {
+    let grid = day4::parse("ABA\nB.B\nABA\n").unwrap();
+    assert_eq!(day4::count_word(&day4::parse("ABA\n").unwrap(), "ABA"), 1);
+    // Two rows, two columns and neither diagonal.
+    assert_eq!(day4::count_word(&grid, "ABA"), 4);
+    assert_eq!(day4::count_word(&grid, "A"), 4);
+    assert_eq!(day4::count_word(&grid, "AB"), 8);
+}
+
+fn variant_count(specification: &str) -> usize {
+    Template::parse(specification).unwrap().variants().len()
+}
+
+#[test]
+fn symmetric_templates_have_fewer_variants() {
+    assert_eq!(variant_count("A"), 1);
+    assert_eq!(variant_count("AB"), 4);
+    // Both built in templates are their own mirror image.
+    assert_eq!(variant_count("x-mas"), 4);
+    assert_eq!(variant_count("plus"), 4);
+    assert_eq!(variant_count("AB/C."), 8);
+}
+
+#[test]
+fn counts_custom_templates() {
+    let count = |grid: &str, specification: &str| {
+        day4::count_template(
+            &day4::parse(grid).unwrap(),
+            &Template::parse(specification).unwrap(),
+        )
+    };
+    // Once along each row and column, but never diagonally.
+    assert_eq!(count("AB\nBA\n", "AB"), 4);
+    // The wildcard matches the Bs, and the Bs on the other diagonal are not As.
+    assert_eq!(count("AB\nBA\n", "A./.A"), 1);
+    assert_eq!(count(".M.\nMAS\n.S.\n", "plus"), 1);
+    assert_eq!(count(".M.\nMAS\n.S.\n", ".M./MAS/.S.\n"), 1);
+    assert_eq!(count("MAS\nMAS\n", "x-mas"), 0);
+}
+
+#[test]
+fn templates_need_a_letter() {
+    let error = Template::parse("./..").unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+fn listed(grid: &str, words: &[&str], templates: &[&str]) -> String {
+    let words: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+    let templates: Vec<String> = templates.iter().map(|name| name.to_string()).collect();
+    day4::listing(&day4::find_matches(&day4::parse(grid).unwrap(), &words, &templates).unwrap())
+}
+
+#[test]
+fn lists_matches_with_their_orientation() {
+    assert_eq!(
+        listed("M.S\n.A.\nM.S\n", &[], &["x-mas"]),
+        "x-mas at (0, 0) rot0\n"
+    );
+    assert_eq!(
+        listed("M.M\n.A.\nS.S\n", &[], &["x-mas"]),
+        "x-mas at (0, 0) rot90\n"
+    );
+    assert_eq!(
+        listed("S.M\n.A.\nS.M\n", &[], &["x-mas"]),
+        "x-mas at (0, 0) rot0+flip\n"
+    );
+    assert_eq!(
+        listed("XMAS\n.M..\n", &["XMAS", "MA"], &[]),
+        "XMAS at (0, 0) Right\nMA at (0, 1) Right\nMA at (1, 1) RightUp\n"
+    );
+}
+
+#[test]
+fn highlights_only_the_matched_letters() {
+    let grid = day4::parse("XMASX\nSAMXM\n").unwrap();
+    let matches = day4::find_matches(&grid, &["XMAS".to_string()], &[]).unwrap();
+    assert_eq!(matches.len(), 2);
+    assert_eq!(day4::highlighted(&grid, &matches, false), "XMAS.\nSAMX.\n");
+    let coloured = day4::highlighted(&grid, &matches, true);
+    assert!(coloured.starts_with("\x1b[1;32mX\x1b[0m"), "{:?}", coloured);
+    assert!(coloured.contains("\x1b[2m.\x1b[0m\n"), "{:?}", coloured);
+}
+
+proptest! {
+    #[test]
+    fn part_1_matches_oracle(grid in grid()) {
+        let parsed = day4::parse(&input(&grid)).unwrap();
+        prop_assert_eq!(day4::part_1(&parsed).unwrap(), oracle_part_1(&grid));
+    }
+
+    #[test]
+    fn part_2_matches_oracle(grid in grid()) {
+        let parsed = day4::parse(&input(&grid)).unwrap();
+        prop_assert_eq!(day4::part_2(&parsed).unwrap(), oracle_part_2(&grid));
+    }
+}
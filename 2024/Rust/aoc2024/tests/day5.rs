@@ -0,0 +1,242 @@
+use aoc2024::commands::day5;
+use proptest::prelude::*;
+use std::collections::BTreeSet;
+
+fn is_ordered(pages: &[u32], rules: &[(u32, u32)]) -> bool {
+    (0..pages.len()).all(|i| (i + 1..pages.len()).all(|j| !rules.contains(&(pages[j], pages[i]))))
+}
+
+fn permutations(pages: &[u32]) -> Vec<Vec<u32>> {
+    if pages.is_empty() {
+        return vec![vec![]];
+    }
+    let mut permutations = Vec::new();
+    for index in 0..pages.len() {
+        let mut rest = pages.to_vec();
+        let page = rest.remove(index);
+        for mut permutation in self::permutations(&rest) {
+            permutation.insert(0, page);
+            permutations.push(permutation);
+        }
+    }
+    permutations
+}
+
+fn oracle_part_1(rules: &[(u32, u32)], updates: &[Vec<u32>]) -> u32 {
+    updates
+        .iter()
+        .filter(|pages| is_ordered(pages, rules))
+        .map(|pages| pages[pages.len() / 2])
+        .sum()
+}
+
+// Tries every ordering of each misordered update, only valid when the rules order every pair.
+fn oracle_part_2(rules: &[(u32, u32)], updates: &[Vec<u32>]) -> u32 {
+    updates
+        .iter()
+        .filter(|pages| !is_ordered(pages, rules))
+        .map(|pages| {
+            let ordered = permutations(pages)
+                .into_iter()
+                .find(|permutation| is_ordered(permutation, rules))
+                .unwrap();
+            ordered[ordered.len() / 2]
+        })
+        .sum()
+}
+
+fn input(rules: &[(u32, u32)], updates: &[Vec<u32>]) -> String {
+    let rules: Vec<String> = rules
+        .iter()
+        .map(|(first, second)| format!("{}|{}", first, second))
+        .collect();
+    let updates: Vec<String> = updates
+        .iter()
+        .map(|pages| {
+            pages
+                .iter()
+                .map(|page| page.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        })
+        .collect();
+    format!("{}\n\n{}\n", rules.join("\n"), updates.join("\n"))
+}
+
+// Rules that order every pair of pages, like the real puzzle, with updates drawn from those pages.
+fn total_order() -> impl Strategy<Value = (Vec<(u32, u32)>, Vec<Vec<u32>>)> {
+    Just((10u32..30).collect::<Vec<u32>>())
+        .prop_shuffle()
+        .prop_flat_map(|order| {
+            let rules: Vec<(u32, u32)> = (0..order.len())
+                .flat_map(|i| (i + 1..order.len()).map(move |j| (i, j)))
+                .map(|(i, j)| (order[i], order[j]))
+                .collect();
+            let update = prop::sample::subsequence(order, 1..7).prop_shuffle();
+            (
+                Just(rules).prop_shuffle(),
+                prop::collection::vec(update, 1..10),
+            )
+        })
+}
+
+// Pages that can reach each other through the rules, largest group first like the report, and
+// whether no page can reach itself.
+fn oracle_components(rules: &[(u32, u32)]) -> (Vec<Vec<u32>>, bool) {
+    let pages: BTreeSet<u32> = rules.iter().flat_map(|&(a, b)| [a, b]).collect();
+    let reaches = |from: u32, to: u32| {
+        let mut seen = BTreeSet::new();
+        let mut next = vec![from];
+        while let Some(page) = next.pop() {
+            for &(a, b) in rules {
+                if a == page && seen.insert(b) {
+                    next.push(b);
+                }
+            }
+        }
+        seen.contains(&to)
+    };
+    let mut components: Vec<Vec<u32>> = Vec::new();
+    for &page in &pages {
+        match components
+            .iter_mut()
+            .find(|component| reaches(component[0], page) && reaches(page, component[0]))
+        {
+            Some(component) => component.push(page),
+            None => components.push(vec![page]),
+        }
+    }
+    components.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+    (components, pages.iter().all(|&page| !reaches(page, page)))
+}
+
+// Any rules at all, including ones that contradict each other through a cycle.
+fn arbitrary_rules() -> impl Strategy<Value = (Vec<(u32, u32)>, Vec<Vec<u32>>)> {
+    let pages = (10u32..18).collect::<Vec<u32>>();
+    let rule =
+        (10u32..18, 10u32..18).prop_filter("a page is not ordered against itself", |(a, b)| a != b);
+    let update = prop::sample::subsequence(pages, 1..7).prop_shuffle();
+    (
+        prop::collection::vec(rule, 1..30),
+        prop::collection::vec(update, 1..10),
+    )
+}
+
+proptest! {
+    #[test]
+    fn part_1_matches_oracle((rules, updates) in total_order()) {
+        let (parsed_rules, parsed_updates) = day5::parse(&input(&rules, &updates)).unwrap();
+        prop_assert_eq!(day5::part_1(&parsed_rules, &parsed_updates).unwrap(), oracle_part_1(&rules, &updates));
+    }
+
+    #[test]
+    fn part_2_matches_oracle((rules, updates) in total_order()) {
+        let (parsed_rules, parsed_updates) = day5::parse(&input(&rules, &updates)).unwrap();
+        prop_assert_eq!(day5::part_2(&parsed_rules, &parsed_updates).unwrap(), oracle_part_2(&rules, &updates));
+    }
+
+    #[test]
+    fn cyclic_rules_match_oracle((rules, updates) in arbitrary_rules()) {
+        let (parsed_rules, parsed_updates) = day5::parse(&input(&rules, &updates)).unwrap();
+        prop_assert_eq!(day5::part_1(&parsed_rules, &parsed_updates).unwrap(), oracle_part_1(&rules, &updates));
+        // Part 2 has no oracle when the rules contradict each other, it only has to succeed.
+        prop_assert!(day5::part_2(&parsed_rules, &parsed_updates).is_ok());
+
+        let report = day5::graph_report(&parsed_rules, &parsed_updates, None).unwrap();
+        let (components, is_acyclic) = oracle_components(&rules);
+        prop_assert_eq!(report.strongly_connected_components, components);
+        prop_assert_eq!(report.is_acyclic, is_acyclic);
+    }
+}
+
+#[test]
+fn contradictory_rules_do_not_panic() {
+    let (rules, updates) = day5::parse("1|2\n2|3\n3|1\n2|1\n4|4\n\n1,2,3\n3,2,1,4,4\n").unwrap();
+    assert!(day5::part_1(&rules, &updates).is_ok());
+    assert!(day5::part_2(&rules, &updates).is_ok());
+}
+
+fn report(contents: &str) -> day5::GraphReport {
+    let (rules, updates) = day5::parse(contents).unwrap();
+    day5::graph_report(&rules, &updates, None).unwrap()
+}
+
+#[test]
+fn reports_a_two_page_cycle_as_one_component() {
+    let report = report("1|2\n2|1\n2|3\n\n1,2,3\n");
+    assert_eq!(
+        report.strongly_connected_components,
+        vec![vec![1, 2], vec![3]]
+    );
+    assert!(!report.is_acyclic);
+    assert!(report.to_dot().contains(
+        "    subgraph cluster_0 {\n        label=\"component 0\";\n        1;\n        2;\n    }"
+    ));
+}
+
+#[test]
+fn reports_a_self_loop_as_a_cycle() {
+    let report = report("1|1\n1|2\n\n1,2\n");
+    assert_eq!(report.strongly_connected_components, vec![vec![1], vec![2]]);
+    assert!(!report.is_acyclic);
+}
+
+#[test]
+fn reports_a_dag_as_acyclic() {
+    let report = report("1|2\n1|3\n2|3\n3|4\n\n1,2,3,4,5\n");
+    assert_eq!(
+        report.strongly_connected_components,
+        vec![vec![1], vec![2], vec![3], vec![4]]
+    );
+    assert!(report.is_acyclic);
+    assert_eq!(report.unconstrained_pages, vec![5]);
+    assert!(!report.to_dot().contains("subgraph"));
+}
+
+#[test]
+fn explains_the_broken_rules_and_the_corrected_order() {
+    let (rules, updates) = day5::parse("47|53\n97|13\n97|61\n97|47\n75|29\n61|13\n75|53\n29|13\n97|29\n53|29\n61|53\n97|53\n61|29\n47|13\n75|47\n97|75\n47|61\n75|61\n47|29\n75|13\n53|13\n\n75,47,61,53,29\n75,97,47,61,53\n").unwrap();
+    let rule_map = day5::rule_map(&rules);
+
+    assert!(updates[0].is_valid(&rule_map));
+    assert!(updates[0].violations(&rule_map).is_empty());
+    assert_eq!(updates[0].explanation(0, &rule_map), None);
+
+    assert!(!updates[1].is_valid(&rule_map));
+    assert_eq!(
+        updates[1].violations(&rule_map),
+        vec![day5::Violation {
+            rule: day5::Rule {
+                first: 97,
+                second: 75
+            },
+            first_position: 1,
+            second_position: 0,
+        }]
+    );
+    assert_eq!(
+        updates[1].explanation(1, &rule_map).unwrap(),
+        "Update 1: 75,97,47,61,53\n  breaks 97|75: 97 at position 1, 75 at position 0\n  - 75 97 47 61 53\n  + 97 75 47 61 53\n    ^^ ^^\n"
+    );
+}
+
+#[test]
+fn explains_updates_the_rules_cannot_order() {
+    let (rules, updates) = day5::parse("1|2\n2|1\n\n1,2\n").unwrap();
+    let rule_map = day5::rule_map(&rules);
+    assert_eq!(
+        updates[0].explanation(0, &rule_map).unwrap(),
+        "Update 0: 1,2\n  breaks 2|1: 2 at position 1, 1 at position 0\n  - 1 2\n  + 1 2\n  (the rules cannot fully order this update)\n"
+    );
+}
+
+#[test]
+fn long_chains_of_rules_do_not_overflow_the_stack() {
+    let rules: String = (0..200_000)
+        .map(|page| format!("{}|{}\n", page, page + 1))
+        .collect();
+    let report = report(&format!("{}200000|0\n\n1,2\n", rules));
+    assert_eq!(report.strongly_connected_components.len(), 1);
+    assert_eq!(report.strongly_connected_components[0].len(), 200_001);
+    assert!(!report.is_acyclic);
+}
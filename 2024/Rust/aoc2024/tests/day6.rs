@@ -0,0 +1,108 @@
+use aoc2024::commands::day6;
+use proptest::prelude::*;
+use std::collections::HashSet;
+
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+// Walks the guard one step at a time and returns the visited cells, or None when the guard comes
+// back to a cell facing the same way it did before.
+fn walk(grid: &[Vec<char>]) -> Option<HashSet<(i32, i32)>> {
+    let height = grid.len() as i32;
+    let width = grid[0].len() as i32;
+    let y = grid.iter().position(|line| line.contains(&'^')).unwrap();
+    let x = grid[y].iter().position(|&c| c == '^').unwrap();
+    let (mut x, mut y, mut direction) = (x as i32, y as i32, 0);
+    let mut states = HashSet::new();
+    loop {
+        if !states.insert((x, y, direction)) {
+            return None;
+        }
+        let (dx, dy) = DIRECTIONS[direction];
+        let (next_x, next_y) = (x + dx, y + dy);
+        if next_x < 0 || next_x >= width || next_y < 0 || next_y >= height {
+            return Some(states.iter().map(|&(x, y, _)| (x, y)).collect());
+        }
+        if grid[next_y as usize][next_x as usize] == '#' {
+            direction = (direction + 1) % 4;
+        } else {
+            (x, y) = (next_x, next_y);
+        }
+    }
+}
+
+fn oracle_part_1(grid: &[Vec<char>]) -> usize {
+    walk(grid).map(|visited| visited.len()).unwrap_or(0)
+}
+
+// Tries an obstruction on every open cell.
+fn oracle_part_2(grid: &[Vec<char>]) -> usize {
+    let mut count = 0;
+    for y in 0..grid.len() {
+        for x in 0..grid[y].len() {
+            if grid[y][x] == '.' {
+                let mut obstructed = grid.to_vec();
+                obstructed[y][x] = '#';
+                if walk(&obstructed).is_none() {
+                    count += 1;
+                }
+            }
+        }
+    }
+    count
+}
+
+// A sparse map with the guard placed anywhere, including right at the edge.
+fn grid() -> impl Strategy<Value = Vec<Vec<char>>> {
+    (1usize..9, 1usize..9).prop_flat_map(|(height, width)| {
+        let cell = prop::sample::select(vec!['.', '.', '.', '.', '#']);
+        (
+            prop::collection::vec(prop::collection::vec(cell, width), height),
+            0..height,
+            0..width,
+        )
+            .prop_map(|(mut grid, y, x)| {
+                grid[y][x] = '^';
+                grid
+            })
+    })
+}
+
+fn input(grid: &[Vec<char>]) -> String {
+    grid.iter()
+        .map(|line| line.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn part_1_matches_oracle(grid in grid().prop_filter("the guard leaves", |grid| walk(grid).is_some())) {
+        let map = day6::parse(&input(&grid)).unwrap();
+        prop_assert_eq!(day6::part_1(&map).unwrap(), oracle_part_1(&grid));
+    }
+
+    #[test]
+    fn part_2_matches_oracle(grid in grid().prop_filter("the guard leaves", |grid| walk(grid).is_some())) {
+        let map = day6::parse(&input(&grid)).unwrap();
+        prop_assert_eq!(day6::part_2(&map).unwrap(), oracle_part_2(&grid));
+    }
+}
+
+#[test]
+fn map_without_a_guard_is_an_error() {
+    assert!(day6::parse("").is_err());
+    assert!(day6::parse("..#\n...\n").is_err());
+}
+
+#[test]
+fn guard_in_a_loop_is_an_error() {
+    let map = day6::parse(".#...\n....#\n#....\n.^.#.\n").unwrap();
+    assert!(day6::part_1(&map).is_err());
+    assert!(day6::part_2(&map).is_err());
+}
+
+#[test]
+fn ragged_map_treats_missing_cells_as_off_the_board() {
+    let map = day6::parse(".\n..\n.^.\n").unwrap();
+    assert_eq!(day6::part_1(&map).unwrap(), 2);
+    assert_eq!(day6::part_2(&map).unwrap(), 0);
+}
@@ -0,0 +1,89 @@
+use aoc2024::commands::day7;
+use proptest::prelude::*;
+
+// Evaluates every sequence of operators from left to right.
+fn can_make(test_value: i64, factors: &[i64], concatenation: bool) -> bool {
+    let operator_count: u32 = if concatenation { 3 } else { 2 };
+    let combinations = operator_count.pow(factors.len() as u32 - 1);
+    (0..combinations).any(|mut combination| {
+        let mut value = factors[0];
+        for &factor in &factors[1..] {
+            value = match combination % operator_count {
+                0 => value + factor,
+                1 => value * factor,
+                _ => format!("{}{}", value, factor).parse().unwrap(),
+            };
+            combination /= operator_count;
+        }
+        value == test_value
+    })
+}
+
+fn oracle(equations: &[(i64, Vec<i64>)], concatenation: bool) -> i128 {
+    equations
+        .iter()
+        .filter(|(test_value, factors)| can_make(*test_value, factors, concatenation))
+        .map(|(test_value, _)| *test_value as i128)
+        .sum()
+}
+
+// Half the test values are made from the factors with random operators so that many equations can
+// be solved.
+fn equations() -> impl Strategy<Value = Vec<(i64, Vec<i64>)>> {
+    let equation = prop::collection::vec(1i64..20, 1..5).prop_flat_map(|factors| {
+        let operators = prop::collection::vec(0u8..3, factors.len() - 1);
+        (Just(factors), operators, any::<bool>(), 0i64..2000).prop_map(
+            |(factors, operators, is_made, random_value)| {
+                let mut value = factors[0];
+                for (&factor, operator) in factors[1..].iter().zip(operators) {
+                    value = match operator {
+                        0 => value + factor,
+                        1 => value * factor,
+                        _ => format!("{}{}", value, factor).parse().unwrap(),
+                    };
+                }
+                (if is_made { value } else { random_value }, factors)
+            },
+        )
+    });
+    prop::collection::vec(equation, 1..15)
+}
+
+fn input(equations: &[(i64, Vec<i64>)]) -> String {
+    equations
+        .iter()
+        .map(|(test_value, factors)| {
+            let factors: Vec<String> = factors.iter().map(|factor| factor.to_string()).collect();
+            format!("{}: {}\n", test_value, factors.join(" "))
+        })
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn part_1_matches_oracle(equations in equations()) {
+        let parsed = day7::parse(&input(&equations)).unwrap();
+        prop_assert_eq!(day7::part_1(&parsed).unwrap(), oracle(&equations, false));
+    }
+
+    #[test]
+    fn part_2_matches_oracle(equations in equations()) {
+        let parsed = day7::parse(&input(&equations)).unwrap();
+        prop_assert_eq!(day7::part_2(&parsed).unwrap(), oracle(&equations, true));
+    }
+}
+
+#[test]
+fn overflowing_equations_are_not_valid() {
+    let equations = day7::parse("9223372036854775807: 9223372036854775807 2 3\n").unwrap();
+    assert_eq!(day7::part_2(&equations).unwrap(), 0);
+}
+
+#[test]
+fn sum_wider_than_an_i64() {
+    let equations = day7::parse(
+        "9223372036854775807: 9223372036854775807\n9223372036854775807: 9223372036854775807\n",
+    )
+    .unwrap();
+    assert_eq!(day7::part_1(&equations).unwrap(), 2 * i64::MAX as i128);
+}
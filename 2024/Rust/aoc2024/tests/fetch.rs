@@ -0,0 +1,86 @@
+mod common;
+
+use aoc2024::args::SiteArgs;
+use aoc2024::fetch::{cached_input, fetch};
+use aoc2024::site::{Config, Site};
+use common::{Stub, TestSite};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+fn config(stub: &Stub, session: Option<&str>, request_interval: f64) -> Config {
+    Config {
+        session: session.map(str::to_string),
+        contact: Some("someone@example.com".to_string()),
+        problems: Some(PathBuf::from("problems")),
+        request_interval: Some(request_interval),
+        ..stub.config()
+    }
+}
+
+#[test]
+fn downloads_an_input_once_and_then_uses_the_cache() {
+    let stub = Stub::start(|request| match request.path.as_str() {
+        "/2024/day/3/input" => (200, "mul(2,4)\n".to_string()),
+        _ => (404, "Not Found".to_string()),
+    });
+    let test_site = TestSite::new("fetch", config(&stub, Some("abc"), 0.0));
+    let (site, directory) = (&test_site.site, &test_site.directory);
+
+    let path = fetch(site, 3).unwrap();
+    assert_eq!(path, directory.join("problems").join("day3-1.txt"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "mul(2,4)\n");
+    {
+        let requests = stub.requests.lock().unwrap();
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].header("cookie"), Some("session=abc"));
+        let user_agent = requests[0].header("user-agent").unwrap();
+        assert!(user_agent.starts_with("aoc2024/"), "{}", user_agent);
+        assert!(user_agent.contains("someone@example.com"), "{}", user_agent);
+    }
+
+    // Cached inputs need neither the site nor a session.
+    let offline = test_site.with_config(config(&stub, None, 0.0));
+    assert_eq!(fetch(&offline, 3).unwrap(), path);
+    assert_eq!(stub.request_count(), 1);
+
+    let error = fetch(site, 4).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    assert!(!directory.join("problems").join("day4-1.txt").exists());
+    assert_eq!(
+        fetch(&offline, 5).unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+}
+
+#[test]
+fn waits_between_requests() {
+    let stub = Stub::start(|_| (200, "input\n".to_string()));
+    let test_site = TestSite::new("fetch-rate", config(&stub, Some("abc"), 0.5));
+    let site = &test_site.site;
+
+    let start = Instant::now();
+    fetch(site, 1).unwrap();
+    fetch(site, 2).unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(500));
+    assert_eq!(stub.request_count(), 2);
+}
+
+#[test]
+fn caches_in_the_repository_by_default() {
+    let stub = Stub::start(|_| (404, "Not Found".to_string()));
+    let test_site = TestSite::new("fetch-default", stub.config());
+    let config = test_site.directory.join("aoc.toml");
+    fs::write(&config, "session = \"abc\"\n").unwrap();
+    let site = Site::from_args(&SiteArgs {
+        config,
+        base_url: Some(stub.base_url.clone()),
+    })
+    .unwrap();
+
+    let expected = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../problems/day1-1.txt");
+    assert_eq!(cached_input(&site, 1), expected);
+    assert_eq!(fetch(&site, 1).unwrap(), expected);
+    assert_eq!(stub.request_count(), 0);
+}
@@ -0,0 +1,30 @@
+use aoc2024::commands;
+use aoc2024::generate::generate;
+use aoc2024::input::normalize;
+
+// How a teammate's editor on Windows might save the same input.
+fn as_saved_on_windows(contents: &str) -> String {
+    let lines: Vec<String> = contents.lines().map(|line| format!("{}  ", line)).collect();
+    format!("\u{feff}{}\r\n\r\n", lines.join("\r\n"))
+}
+
+#[test]
+fn normalize_accepts_common_variations() {
+    assert_eq!(normalize("1 2\r\n3 4"), "1 2\n3 4\n");
+    assert_eq!(normalize("\n\n1 2  \n3 4\t\n\n\n"), "1 2\n3 4\n");
+    assert_eq!(normalize("1|2\r\n\r\n\r\n1,2\r\n"), "1|2\n\n1,2\n");
+    assert_eq!(normalize(""), "");
+}
+
+#[test]
+fn every_day_gives_the_same_answer_for_a_windows_copy() {
+    for day in 1..=7 {
+        let input = generate(day, 7, Some(12)).unwrap().input;
+        for part_number in 1..=2 {
+            let expected = commands::solve(day, &input, part_number).unwrap().answer;
+            let windows = normalize(&as_saved_on_windows(&input));
+            let answer = commands::solve(day, &windows, part_number).unwrap().answer;
+            assert_eq!(answer, expected, "day {} part {}", day, part_number);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use aoc2024::args::LeaderboardFormat;
+use aoc2024::leaderboard::{download, local_scores, parse, report};
+use common::{Stub, TestSite};
+use std::collections::BTreeMap;
+
+// Day 1 unlocked at 1733029200 and day 2 a day later. Bob's score on the site is out of date.
+const EXPORT: &str = r#"{
+  "event": "2024",
+  "owner_id": 1,
+  "members": {
+    "1": {"id": 1, "name": "Alice", "stars": 3, "local_score": 7, "global_score": 0,
+          "last_star_ts": 1733116600,
+          "completion_day_level": {
+            "1": {"1": {"get_star_ts": 1733029300, "star_index": 10},
+                  "2": {"get_star_ts": 1733029500, "star_index": 13}},
+            "2": {"1": {"get_star_ts": 1733116600, "star_index": 21}}}},
+    "2": {"id": 2, "name": "Bob", "stars": 2, "local_score": 4, "global_score": 0,
+          "last_star_ts": 1733029600,
+          "completion_day_level": {
+            "1": {"1": {"get_star_ts": 1733029250, "star_index": 9},
+                  "2": {"get_star_ts": 1733029600, "star_index": 14}}}},
+    "3": {"id": 3, "name": null, "stars": 2, "local_score": 6, "global_score": 0,
+          "last_star_ts": 1733116200,
+          "completion_day_level": {
+            "2": {"1": {"get_star_ts": 1733116100, "star_index": 20},
+                  "2": {"get_star_ts": 1733116200, "star_index": 22}}}}
+  }
+}"#;
+
+#[test]
+fn recomputes_the_local_scores() {
+    let leaderboard = parse(EXPORT).unwrap();
+    assert_eq!(
+        local_scores(&leaderboard),
+        BTreeMap::from([(1, 7), (2, 5), (3, 6)])
+    );
+}
+
+#[test]
+fn reports_standings_and_star_times() {
+    let leaderboard = parse(EXPORT).unwrap();
+    let markdown = report(&leaderboard, LeaderboardFormat::Markdown).unwrap();
+    let expected = [
+        "## Standings 2024",
+        "| Rank | Name | Days | Stars | Score |",
+        "| --- | --- | --- | ---: | ---: |",
+        "| 1. | Alice | *+ | 3 | 7 |",
+        "| 2. | (anonymous user #3) | .* | 2 | 6 |",
+        "| 3. | Bob | *. | 2 | 5 |",
+        "## Day 1",
+        "| Alice | 0:01:40 | 0:05:00 | 0:03:20 | 5 |",
+        "| Bob | 0:00:50 | 0:06:40 | 0:05:50 | 5 |",
+        "## Day 2",
+        "| (anonymous user #3) | 0:08:20 | 0:10:00 | 0:01:40 | 6 |",
+        "| Alice | 0:16:40 |  |  | 2 |",
+    ];
+    let mut rest = markdown.as_str();
+    for line in expected {
+        let position = rest
+            .find(line)
+            .unwrap_or_else(|| panic!("{:?} is missing or out of order in\n{}", line, markdown));
+        rest = &rest[position + line.len()..];
+    }
+
+    let table = report(&leaderboard, LeaderboardFormat::Table).unwrap();
+    assert!(table.starts_with("Standings 2024\nRank  Name                 Days  Stars  Score\n1.    Alice                *+        3      7\n"), "{}", table);
+}
+
+#[test]
+fn downloads_at_most_every_fifteen_minutes() {
+    let stub = Stub::start(|_| (200, EXPORT.to_string()));
+    let test_site = TestSite::new("leaderboard", stub.config());
+    let site = &test_site.site;
+
+    assert_eq!(download(site, 42).unwrap(), EXPORT);
+    assert_eq!(download(site, 42).unwrap(), EXPORT);
+    assert_eq!(stub.request_count(), 1);
+    assert_eq!(
+        stub.requests.lock().unwrap()[0].path,
+        "/2024/leaderboard/private/view/42.json"
+    );
+}
@@ -0,0 +1,58 @@
+use aoc2024::generate::generate;
+use aoc2024::output::record;
+use std::env;
+use std::fs;
+
+#[test]
+fn records_the_answer_timings_and_hash() {
+    let generated = generate(1, 0, Some(50)).unwrap();
+    let path = env::temp_dir().join(format!("aoc2024-output-{}.txt", std::process::id()));
+    fs::write(&path, &generated.input).unwrap();
+
+    let part_1 = record(1, 1, &path);
+    let part_2 = record(1, 2, &path);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(part_1.error, None);
+    assert_eq!(part_1.answer, generated.answers[0]);
+    assert_eq!(part_2.answer, generated.answers[1]);
+    assert!(part_1.parse_nanoseconds.is_some() && part_1.solve_nanoseconds.is_some());
+    let hash = part_1.input_sha256.unwrap();
+    assert_eq!(hash.len(), 64);
+    assert_eq!(Some(hash), part_2.input_sha256);
+}
+
+#[test]
+fn hashes_a_streamed_input() {
+    let generated = generate(3, 0, Some(200)).unwrap();
+    let path = env::temp_dir().join(format!("aoc2024-output-3-{}.txt", std::process::id()));
+    fs::write(&path, &generated.input).unwrap();
+
+    let streamed = record(3, 2, &path);
+    let read = record(1, 1, &path);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(streamed.error, None);
+    assert_eq!(streamed.answer, generated.answers[1]);
+    assert_eq!(streamed.parse_nanoseconds, Some(0));
+    assert_eq!(streamed.input_sha256, read.input_sha256);
+    assert!(read.error.is_some());
+}
+
+#[test]
+fn records_the_error_instead_of_an_answer() {
+    let missing = record(1, 1, &env::temp_dir().join("aoc2024-output-missing.txt"));
+    assert!(missing.error.is_some());
+    assert_eq!(missing.input_sha256, None);
+
+    let path = env::temp_dir().join(format!("aoc2024-output-bad-{}.txt", std::process::id()));
+    fs::write(&path, "not a map\n").unwrap();
+    let invalid = record(6, 1, &path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(invalid.answer, None);
+    assert!(invalid.input_sha256.is_some());
+    assert_eq!(
+        invalid.error.as_deref(),
+        Some("The map has no guard, marked with ^")
+    );
+}
@@ -0,0 +1,60 @@
+use aoc2024::parsing::{
+    comma_separated, grid, key_values, lines, parse_all, sections, signed, unsigned,
+    whitespace_separated,
+};
+
+#[test]
+fn integers_of_any_width() {
+    assert_eq!(parse_all("255", "a byte", unsigned::<u8>).unwrap(), 255);
+    assert!(parse_all("256", "a byte", unsigned::<u8>).is_err());
+    assert_eq!(parse_all("-12", "a number", signed::<i64>).unwrap(), -12);
+    assert_eq!(parse_all("+12", "a number", signed::<i16>).unwrap(), 12);
+    assert!(parse_all("-12", "a number", unsigned::<u32>).is_err());
+}
+
+#[test]
+fn lists_records_and_sections() {
+    let records = parse_all(
+        "1 2\n3 -4\n",
+        "records",
+        lines(whitespace_separated(signed::<i32>)),
+    );
+    assert_eq!(records.unwrap(), vec![vec![1, 2], vec![3, -4]]);
+
+    let (first, second) = parse_all(
+        "1\n2\n\n3,4\n",
+        "two sections",
+        sections(
+            lines(unsigned::<u8>),
+            lines(comma_separated(unsigned::<u8>)),
+        ),
+    )
+    .unwrap();
+    assert_eq!(first, vec![1, 2]);
+    assert_eq!(second, vec![vec![3, 4]]);
+
+    let line = parse_all(
+        "190: 10 19",
+        "an equation",
+        key_values(unsigned::<u64>, whitespace_separated(unsigned::<u64>)),
+    );
+    assert_eq!(line.unwrap(), (190, vec![10, 19]));
+
+    assert_eq!(
+        parse_all("ab\nc\n", "a grid", grid).unwrap(),
+        vec!["ab", "c"]
+    );
+}
+
+#[test]
+fn errors_point_at_the_line() {
+    let error = parse_all(
+        "1 2\n3 x\n",
+        "records",
+        lines(whitespace_separated(signed::<i32>)),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(error.starts_with("The input is not records"), "{}", error);
+    assert!(error.contains("line 2"), "{}", error);
+}
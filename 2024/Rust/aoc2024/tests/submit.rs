@@ -0,0 +1,203 @@
+mod common;
+
+use aoc2024::answers::{AnswerStore, Reason};
+use aoc2024::generate::generate;
+use aoc2024::submit::{parse_response, submit, Verdict};
+use common::{Stub, TestSite};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+fn page(message: &str) -> String {
+    format!(
+        "<html><body><main>\n<article><p>{}</p></article>\n</main></body></html>",
+        message
+    )
+}
+
+#[test]
+fn parses_the_replies() {
+    let correct = parse_response(&page(
+        "That's the right answer!  You are <span class=\"day-success\">one gold star</span> closer.",
+    ));
+    assert_eq!(correct.verdict, Verdict::Correct);
+    assert_eq!(
+        correct.message,
+        "That's the right answer! You are one gold star closer."
+    );
+
+    let too_high = parse_response(&page(
+        "That's not the right answer; your answer is too high.  Please wait one minute before trying again.",
+    ));
+    assert_eq!(too_high.verdict, Verdict::Rejected(Reason::TooHigh));
+    assert_eq!(too_high.wait, Some(Duration::from_secs(60)));
+
+    let too_low = parse_response(&page(
+        "That's not the right answer; your answer is too low.",
+    ));
+    assert_eq!(too_low.verdict, Verdict::Rejected(Reason::TooLow));
+    assert_eq!(too_low.wait, None);
+
+    let wrong = parse_response(&page(
+        "That's not the right answer.  Because you have guessed incorrectly 4 times on this puzzle, please wait 5 minutes before trying again.",
+    ));
+    assert_eq!(wrong.verdict, Verdict::Rejected(Reason::Wrong));
+    assert_eq!(wrong.wait, Some(Duration::from_secs(300)));
+
+    let limited = parse_response(&page(
+        "You gave an answer too recently; you have to wait after submitting an answer before trying again.  You have 1m 5s left to wait.",
+    ));
+    assert_eq!(
+        limited.verdict,
+        Verdict::RateLimited(Duration::from_secs(65))
+    );
+    assert_eq!(limited.wait, Some(Duration::from_secs(65)));
+
+    let solved = parse_response(&page(
+        "You don't seem to be solving the right level.  Did you already complete it?",
+    ));
+    assert_eq!(solved.verdict, Verdict::AlreadySolved);
+    assert_eq!(parse_response("Something else").verdict, Verdict::Unknown);
+}
+
+struct Setup {
+    input_file: PathBuf,
+    answers: PathBuf,
+    test_site: TestSite,
+}
+
+fn setup(name: &str, stub: &Stub) -> (Setup, [String; 2]) {
+    let test_site = TestSite::new(&format!("submit-{}", name), stub.config());
+    let generated = generate(1, 5, Some(30)).unwrap();
+    let input_file = test_site.directory.join("day1-1.txt");
+    fs::write(&input_file, &generated.input).unwrap();
+    let setup = Setup {
+        answers: test_site.directory.join("answers.toml"),
+        input_file,
+        test_site,
+    };
+    let [part_1, part_2] = generated.answers;
+    (setup, [part_1.unwrap(), part_2.unwrap()])
+}
+
+fn store(path: &Path) -> AnswerStore {
+    AnswerStore::load(path).unwrap()
+}
+
+#[test]
+fn records_accepted_answers_and_never_submits_them_again() {
+    let stub = Stub::start(|_| (200, page("That's the right answer!")));
+    let (setup, answers) = setup("correct", &stub);
+
+    let response = submit(
+        &setup.test_site.site,
+        &mut store(&setup.answers),
+        &setup.input_file,
+        1,
+        1,
+    )
+    .unwrap();
+    assert_eq!(response.verdict, Verdict::Correct);
+    {
+        let requests = stub.requests.lock().unwrap();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/2024/day/1/answer");
+        assert_eq!(requests[0].body, format!("level=1&answer={}", answers[0]));
+    }
+    let recorded = store(&setup.answers);
+    let entry = recorded.get(1, 1, &setup.input_file).unwrap().unwrap();
+    assert_eq!(entry.answer, answers[0]);
+
+    let again = submit(
+        &setup.test_site.site,
+        &mut store(&setup.answers),
+        &setup.input_file,
+        1,
+        1,
+    )
+    .unwrap();
+    assert_eq!(again.verdict, Verdict::Correct);
+    assert_eq!(stub.request_count(), 1);
+}
+
+#[test]
+fn never_resubmits_wrong_answers() {
+    let stub = Stub::start(|_| {
+        (
+            200,
+            page("That's not the right answer; your answer is too high."),
+        )
+    });
+    let (setup, _) = setup("wrong", &stub);
+
+    let response = submit(
+        &setup.test_site.site,
+        &mut store(&setup.answers),
+        &setup.input_file,
+        1,
+        2,
+    )
+    .unwrap();
+    assert_eq!(response.verdict, Verdict::Rejected(Reason::TooHigh));
+    let saved = fs::read_to_string(&setup.answers).unwrap();
+    assert!(saved.contains("[[rejected]]"), "{}", saved);
+    assert!(saved.contains("reason = \"too_high\""), "{}", saved);
+
+    let error = submit(
+        &setup.test_site.site,
+        &mut store(&setup.answers),
+        &setup.input_file,
+        1,
+        2,
+    )
+    .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert!(error.to_string().contains("too high"), "{}", error);
+    assert_eq!(stub.request_count(), 1);
+
+    // Anything at least as big as an answer that was too high is wrong too.
+    let known = store(&setup.answers);
+    assert!(known
+        .known_wrong(1, 2, &setup.input_file, "99999999999")
+        .unwrap()
+        .is_some());
+    assert!(known
+        .known_wrong(1, 2, &setup.input_file, "0")
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn waits_as_long_as_the_site_asks() {
+    let stub = Stub::start(|_| {
+        (
+            200,
+            page("You gave an answer too recently; you have to wait after submitting an answer before trying again.  You have 30s left to wait."),
+        )
+    });
+    let (setup, _) = setup("limited", &stub);
+
+    let response = submit(
+        &setup.test_site.site,
+        &mut store(&setup.answers),
+        &setup.input_file,
+        1,
+        1,
+    )
+    .unwrap();
+    assert_eq!(
+        response.verdict,
+        Verdict::RateLimited(Duration::from_secs(30))
+    );
+    let error = submit(
+        &setup.test_site.site,
+        &mut store(&setup.answers),
+        &setup.input_file,
+        1,
+        1,
+    )
+    .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+    assert_eq!(stub.request_count(), 1);
+}